@@ -0,0 +1,507 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+
+use crate::history::NameHistory;
+
+// Confirmed and rejected alt groups, next to dd_settings.cfg
+pub const ALT_REVIEWS_FILE: &str = "dd_alt_reviews.json";
+// Names, clan tags and co-players shared by more accounts than this are too common to mean anything
+const MAX_BUCKET: usize = 25;
+// Co-players seen in at least this many of a player's demos make up their group
+const MIN_GROUP_DEMOS: usize = 3;
+// Overlap of two groups (jaccard) below this isn't "the same people"
+const MIN_GROUP_OVERLAP: f32 = 0.3;
+// Cosine similarity of detection counts per algorithm
+const MIN_DETECTION_SIMILARITY: f32 = 0.8;
+// A pair needs this much evidence to end up in a cluster
+const MIN_SCORE: f32 = 1.0;
+// Clan tags and name stems shorter than this are too generic
+const MIN_STEM_LEN: usize = 4;
+
+/// Why two accounts might belong to the same person
+#[derive(Clone, Debug)]
+pub enum Signal {
+    SharedName(String),
+    NameStyle(String), // same name once tags, digits and symbols are stripped
+    ClanTag(String),
+    SameGroup(f32),
+    SimilarDetections(f32),
+}
+
+impl Signal {
+    fn weight(&self) -> f32 {
+        match self {
+            Signal::SharedName(_) => 1.0,
+            Signal::NameStyle(_) => 0.6,
+            Signal::ClanTag(_) => 0.3,
+            Signal::SameGroup(overlap) => 1.5 * overlap,
+            Signal::SimilarDetections(similarity) => 0.3 * similarity,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Signal::SharedName(name) => format!("both used the name \"{}\"", name),
+            Signal::NameStyle(stem) => format!("names built around \"{}\"", stem),
+            Signal::ClanTag(tag) => format!("same clan tag [{}]", tag),
+            Signal::SameGroup(overlap) => format!("{:.0}% of their regular co-players are the same", overlap * 100.0),
+            Signal::SimilarDetections(similarity) => format!("detection profiles {:.0}% alike", similarity * 100.0),
+        }
+    }
+}
+
+/// Evidence linking two accounts of a cluster
+#[derive(Clone, Debug)]
+pub struct AltLink {
+    pub a: String,
+    pub b: String,
+    pub signals: Vec<Signal>,
+    pub score: f32,
+}
+
+/// Accounts that look like one person, for a reviewer to confirm or reject
+#[derive(Clone, Debug)]
+pub struct AltCluster {
+    pub members: Vec<String>, // Steam32, sorted
+    pub links: Vec<AltLink>,  // strongest first
+    pub confirmed: bool,
+}
+
+impl AltCluster {
+    pub fn score(&self) -> f32 {
+        self.links.first().map_or(0.0, |link| link.score)
+    }
+}
+
+/// What reviewers decided, kept across runs
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AltReviews {
+    #[serde(default)]
+    pub confirmed: Vec<Vec<String>>,
+    #[serde(default)]
+    pub rejected: Vec<(String, String)>, // pairs that are never linked again
+}
+
+impl AltReviews {
+    /// Reviews saved so far, empty when there's no file yet. A file that can't be parsed
+    /// is moved aside first so the next save doesn't overwrite the reviews in it
+    pub fn load() -> Result<Self, String> {
+        let text = match std::fs::read_to_string(ALT_REVIEWS_FILE) {
+            Ok(text) => text,
+            Err(_) => return Ok(Self::default()),
+        };
+        serde_json::from_str(&text).map_err(|e| {
+            let backup = format!("{}.bak", ALT_REVIEWS_FILE);
+            match std::fs::rename(ALT_REVIEWS_FILE, &backup) {
+                Ok(()) => format!("Failed to read {}: {}, moved it to {}", ALT_REVIEWS_FILE, e, backup),
+                Err(rename_error) => format!("Failed to read {}: {}, and failed to move it to {}: {}", ALT_REVIEWS_FILE, e, backup, rename_error),
+            }
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(ALT_REVIEWS_FILE, text).map_err(|e| format!("Failed to write {}: {}", ALT_REVIEWS_FILE, e))
+    }
+
+    /// Confirm a cluster, merging it with confirmed groups it overlaps
+    pub fn confirm(&mut self, members: &[String]) {
+        let mut group: Vec<String> = members.to_vec();
+        self.confirmed.retain(|existing| {
+            if existing.iter().any(|steamid| members.contains(steamid)) {
+                group.extend(existing.iter().cloned());
+                false
+            } else {
+                true
+            }
+        });
+        group.sort();
+        group.dedup();
+        self.confirmed.push(group);
+    }
+
+    /// Reject every link inside a cluster
+    pub fn reject(&mut self, members: &[String]) {
+        for (i, a) in members.iter().enumerate() {
+            for b in &members[i + 1..] {
+                let pair = ordered(a, b);
+                if !self.is_rejected(&pair.0, &pair.1) {
+                    self.rejected.push(pair);
+                }
+            }
+        }
+    }
+
+    fn is_rejected(&self, a: &str, b: &str) -> bool {
+        self.rejected.iter().any(|(x, y)| (x == a && y == b) || (x == b && y == a))
+    }
+
+    /// Other accounts confirmed as the same person as `steamid`
+    pub fn alts_of(&self, steamid: &str) -> Vec<String> {
+        self.confirmed.iter()
+            .find(|group| group.iter().any(|member| member == steamid))
+            .map(|group| group.iter().filter(|member| *member != steamid).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Everything the detector looks at
+pub struct AltInput<'a> {
+    pub name_histories: &'a HashMap<String, NameHistory>,
+    pub demo_players: &'a HashMap<PathBuf, Vec<String>>,
+    pub detections: &'a HashMap<String, HashMap<String, usize>>, // Steam32 -> algorithm -> count
+}
+
+/// Propose alt clusters. Accounts that were ever in the same demo are never linked,
+/// one person can't play on two accounts at once
+pub fn find_alt_clusters(input: &AltInput, reviews: &AltReviews) -> Vec<AltCluster> {
+    let mut player_demos: HashMap<&str, HashSet<&PathBuf>> = HashMap::new();
+    for (demo, players) in input.demo_players {
+        for steamid in players {
+            player_demos.entry(steamid.as_str()).or_default().insert(demo);
+        }
+    }
+    let groups = co_player_groups(input.demo_players, &player_demos);
+
+    // Candidate pairs from accounts sharing a name, a name stem, a clan tag or a regular co-player
+    let mut names: HashMap<String, HashSet<&str>> = HashMap::new();
+    let mut stems: HashMap<String, HashSet<&str>> = HashMap::new();
+    let mut tags: HashMap<String, HashSet<&str>> = HashMap::new();
+    for (steamid, history) in input.name_histories {
+        for name in history.names() {
+            let normalized = name.trim().to_lowercase();
+            if !normalized.is_empty() {
+                names.entry(normalized).or_default().insert(steamid.as_str());
+            }
+            if let Some(stem) = name_stem(name) {
+                stems.entry(stem).or_default().insert(steamid.as_str());
+            }
+            if let Some(tag) = clan_tag(name) {
+                tags.entry(tag).or_default().insert(steamid.as_str());
+            }
+        }
+    }
+    let mut followers: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for (steamid, group) in &groups {
+        for co_player in group {
+            followers.entry(*co_player).or_default().insert(*steamid);
+        }
+    }
+
+    let mut candidates: HashMap<(String, String), Vec<Signal>> = HashMap::new();
+    for (name, steamids) in &names {
+        add_bucket(&mut candidates, steamids, || Signal::SharedName(name.clone()));
+    }
+    for (stem, steamids) in &stems {
+        add_bucket(&mut candidates, steamids, || Signal::NameStyle(stem.clone()));
+    }
+    for (tag, steamids) in &tags {
+        add_bucket(&mut candidates, steamids, || Signal::ClanTag(tag.clone()));
+    }
+    for steamids in followers.values() {
+        add_bucket(&mut candidates, steamids, || Signal::SameGroup(0.0));
+    }
+
+    // Score each pair on everything that applies to it
+    let mut links = Vec::new();
+    for ((a, b), bucket_signals) in candidates {
+        if reviews.is_rejected(&a, &b) {
+            continue;
+        }
+        if let (Some(demos_a), Some(demos_b)) = (player_demos.get(a.as_str()), player_demos.get(b.as_str())) {
+            if !demos_a.is_disjoint(demos_b) {
+                continue;
+            }
+        }
+
+        let mut signals: Vec<Signal> = Vec::new();
+        for signal in bucket_signals {
+            let duplicate = match &signal {
+                Signal::SameGroup(_) => true, // scored below from the actual overlap
+                // A shared name already covers its stem
+                Signal::NameStyle(_) => signals.iter().any(|known| matches!(known, Signal::SharedName(_) | Signal::NameStyle(_))),
+                Signal::ClanTag(_) => signals.iter().any(|known| matches!(known, Signal::ClanTag(_))),
+                _ => false,
+            };
+            if !duplicate {
+                signals.push(signal);
+            }
+        }
+        if let (Some(group_a), Some(group_b)) = (groups.get(a.as_str()), groups.get(b.as_str())) {
+            let overlap = jaccard(group_a, group_b, &a, &b);
+            if overlap >= MIN_GROUP_OVERLAP {
+                signals.push(Signal::SameGroup(overlap));
+            }
+        }
+        if let (Some(profile_a), Some(profile_b)) = (input.detections.get(&a), input.detections.get(&b)) {
+            let similarity = cosine(profile_a, profile_b);
+            if similarity >= MIN_DETECTION_SIMILARITY {
+                signals.push(Signal::SimilarDetections(similarity));
+            }
+        }
+
+        let score: f32 = signals.iter().map(Signal::weight).sum();
+        if score >= MIN_SCORE {
+            links.push(AltLink { a, b, signals, score });
+        }
+    }
+
+    clusters(links, reviews)
+}
+
+/// Regular co-players of everyone with enough demos
+fn co_player_groups<'a>(
+    demo_players: &'a HashMap<PathBuf, Vec<String>>,
+    player_demos: &HashMap<&'a str, HashSet<&'a PathBuf>>,
+) -> HashMap<&'a str, HashSet<&'a str>> {
+    let mut groups = HashMap::new();
+    for (steamid, demos) in player_demos {
+        if demos.len() < MIN_GROUP_DEMOS {
+            continue;
+        }
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for demo in demos {
+            for other in &demo_players[*demo] {
+                if other != steamid {
+                    *counts.entry(other.as_str()).or_default() += 1;
+                }
+            }
+        }
+        let group: HashSet<&str> = counts.into_iter()
+            .filter(|(_, count)| *count >= MIN_GROUP_DEMOS)
+            .map(|(other, _)| other)
+            .collect();
+        if !group.is_empty() {
+            groups.insert(*steamid, group);
+        }
+    }
+    groups
+}
+
+fn add_bucket(candidates: &mut HashMap<(String, String), Vec<Signal>>, steamids: &HashSet<&str>, signal: impl Fn() -> Signal) {
+    if steamids.len() < 2 || steamids.len() > MAX_BUCKET {
+        return;
+    }
+    let mut sorted: Vec<&str> = steamids.iter().copied().collect();
+    sorted.sort();
+    for (i, a) in sorted.iter().enumerate() {
+        for b in &sorted[i + 1..] {
+            candidates.entry(ordered(a, b)).or_default().push(signal());
+        }
+    }
+}
+
+/// Link pairs into clusters with union-find
+fn clusters(mut links: Vec<AltLink>, reviews: &AltReviews) -> Vec<AltCluster> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut parent: Vec<usize> = Vec::new();
+    for link in &links {
+        for steamid in [&link.a, &link.b] {
+            if !index.contains_key(steamid) {
+                index.insert(steamid.clone(), parent.len());
+                parent.push(parent.len());
+            }
+        }
+    }
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for link in &links {
+        let a = root(&mut parent, index[&link.a]);
+        let b = root(&mut parent, index[&link.b]);
+        parent[a] = b;
+    }
+
+    let mut by_root: HashMap<usize, AltCluster> = HashMap::new();
+    let mut members: Vec<(&String, usize)> = index.iter().map(|(steamid, i)| (steamid, *i)).collect();
+    members.sort();
+    for (steamid, i) in members {
+        let cluster = by_root.entry(root(&mut parent, i)).or_insert_with(|| AltCluster {
+            members: Vec::new(),
+            links: Vec::new(),
+            confirmed: false,
+        });
+        cluster.members.push(steamid.clone());
+    }
+    links.sort_by(|x, y| y.score.total_cmp(&x.score));
+    for link in links {
+        let i = root(&mut parent, index[&link.a]);
+        if let Some(cluster) = by_root.get_mut(&i) {
+            cluster.links.push(link);
+        }
+    }
+
+    let mut clusters: Vec<AltCluster> = by_root.into_values().collect();
+    for cluster in &mut clusters {
+        cluster.confirmed = reviews.confirmed.iter()
+            .any(|group| cluster.members.iter().all(|member| group.contains(member)));
+    }
+    clusters.sort_by(|x, y| y.score().total_cmp(&x.score()).then_with(|| x.members.cmp(&y.members)));
+    clusters
+}
+
+/// Clan tag in front of or behind a name, "[TAG] name", "name | TAG", "(TAG)name"
+fn clan_tag(name: &str) -> Option<String> {
+    clan_tag_span(name).map(|span| name[span].trim().to_lowercase())
+}
+
+/// Byte range of the clan tag in `name`, without the brackets
+fn clan_tag_span(name: &str) -> Option<Range<usize>> {
+    let offset = name.len() - name.trim_start().len();
+    let trimmed = name.trim();
+    let bracketed = [('[', ']'), ('(', ')'), ('{', '}'), ('<', '>')].iter().find_map(|(open, close)| {
+        let start = trimmed.find(*open)?;
+        let end = start + trimmed[start..].find(*close)?;
+        let at_edge = start == 0 || end + close.len_utf8() == trimmed.len();
+        at_edge.then(|| start + open.len_utf8()..end)
+    });
+    let span = bracketed.or_else(|| {
+        let bar = trimmed.find('|')?;
+        let (left, right) = (&trimmed[..bar], &trimmed[bar + 1..]);
+        Some(if left.trim().len() <= right.trim().len() { 0..bar } else { bar + 1..trimmed.len() })
+    })?;
+    let tag = trimmed[span.clone()].trim();
+    let valid = tag.chars().filter(|c| c.is_alphanumeric()).count() >= 2 && tag.to_lowercase().len() <= 8;
+    valid.then(|| span.start + offset..span.end + offset)
+}
+
+/// Name without clan tag, symbols and trailing digits, "[TAG] xX_Sniper_Xx99" -> "xxsniperxx"
+fn name_stem(name: &str) -> Option<String> {
+    // Lowercase before looking for the tag, lowercasing can change byte lengths ('K' -> 'k')
+    let mut name = name.trim().to_lowercase();
+    if let Some(span) = clan_tag_span(&name) {
+        name.replace_range(span, "");
+    }
+    let stem: String = name.chars().filter(|c| c.is_alphanumeric()).collect();
+    let stem = stem.trim_end_matches(|c: char| c.is_ascii_digit()).to_string();
+    (stem.chars().count() >= MIN_STEM_LEN).then_some(stem)
+}
+
+fn ordered(a: &str, b: &str) -> (String, String) {
+    if a <= b { (a.to_string(), b.to_string()) } else { (b.to_string(), a.to_string()) }
+}
+
+/// Overlap of two groups, leaving out the pair itself
+fn jaccard(a: &HashSet<&str>, b: &HashSet<&str>, skip_a: &str, skip_b: &str) -> f32 {
+    let keep = |steamid: &&&str| **steamid != skip_a && **steamid != skip_b;
+    let shared = a.iter().filter(keep).filter(|steamid| b.contains(**steamid)).count();
+    let total = a.iter().filter(keep).count() + b.iter().filter(keep).count() - shared;
+    if total == 0 { 0.0 } else { shared as f32 / total as f32 }
+}
+
+fn cosine(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f32 {
+    let dot: f32 = a.iter().map(|(algorithm, count)| (*count * b.get(algorithm).copied().unwrap_or(0)) as f32).sum();
+    let norm = |profile: &HashMap<String, usize>| profile.values().map(|count| (*count * *count) as f32).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn history(names: &[(&str, &str)]) -> NameHistory {
+        let mut history = NameHistory::default();
+        for (name, demo) in names {
+            history.record(name, Path::new(demo), 0);
+        }
+        history.finish();
+        history
+    }
+
+    /// Alt clusters for players as (steamid, name, demo)
+    fn clusters_of(players: &[(&str, &str, &str)], reviews: &AltReviews) -> Vec<AltCluster> {
+        let mut name_histories: HashMap<String, NameHistory> = HashMap::new();
+        let mut demo_players: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        for (steamid, name, demo) in players {
+            name_histories.insert(steamid.to_string(), history(&[(name, demo)]));
+            demo_players.entry(PathBuf::from(demo)).or_default().push(steamid.to_string());
+        }
+        let detections = HashMap::new();
+        let input = AltInput { name_histories: &name_histories, demo_players: &demo_players, detections: &detections };
+        find_alt_clusters(&input, reviews)
+    }
+
+    #[test]
+    fn clan_tags() {
+        assert_eq!(clan_tag("[TAG] name").as_deref(), Some("tag"));
+        assert_eq!(clan_tag("name | TAG").as_deref(), Some("tag"));
+        assert_eq!(clan_tag("(Ärger)name").as_deref(), Some("ärger"));
+        assert_eq!(clan_tag("name <ΣΩ>").as_deref(), Some("σω"));
+        assert_eq!(clan_tag("just a name"), None);
+        assert_eq!(clan_tag("[x] name"), None);
+        assert_eq!(clan_tag("[waytoolongtag] name"), None);
+        assert_eq!(clan_tag("a [tag] in the middle"), None);
+    }
+
+    #[test]
+    fn name_stems() {
+        assert_eq!(name_stem("[TAG] xX_Sniper_Xx99").as_deref(), Some("xxsniperxx"));
+        assert_eq!(name_stem("Sniper | TAG").as_deref(), Some("sniper"));
+        assert_eq!(name_stem("abc123"), None);
+        // Lowercasing these changes their length in bytes
+        assert_eq!(name_stem("\u{212A}\u{212A}sniper [clan]").as_deref(), Some("kksniper"));
+        assert_eq!(name_stem("İİsniper [clan]").as_deref(), Some("iisniper"));
+        assert_eq!(name_stem("[İST] Ölçer").as_deref(), Some("ölçer"));
+        assert_eq!(name_stem("[клан] Снайпер42").as_deref(), Some("снайпер"));
+        // Only the tag itself is removed, not the same letters earlier in the name
+        assert_eq!(name_stem("tagger [tag]").as_deref(), Some("tagger"));
+        assert_eq!(name_stem("tagger | tag").as_deref(), Some("tagger"));
+    }
+
+    #[test]
+    fn jaccard_skips_the_pair() {
+        let a: HashSet<&str> = ["1", "2", "3", "b"].into_iter().collect();
+        let b: HashSet<&str> = ["1", "2", "4", "a"].into_iter().collect();
+        assert_eq!(jaccard(&a, &b, "a", "b"), 0.5);
+        assert_eq!(jaccard(&a, &a, "a", "b"), 1.0);
+        let pair: HashSet<&str> = ["a", "b"].into_iter().collect();
+        assert_eq!(jaccard(&pair, &pair, "a", "b"), 0.0);
+    }
+
+    #[test]
+    fn shared_names_are_clustered() {
+        let clusters = clusters_of(
+            &[("[U:1:1]", "Sniper99", "a.dem"), ("[U:1:2]", "sniper99", "b.dem"), ("[U:1:3]", "medic", "c.dem")],
+            &AltReviews::default(),
+        );
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].members, ["[U:1:1]", "[U:1:2]"]);
+        assert!(matches!(clusters[0].links[0].signals[0], Signal::SharedName(_)));
+        assert!(!clusters[0].confirmed);
+    }
+
+    #[test]
+    fn accounts_in_the_same_demo_are_never_linked() {
+        let clusters = clusters_of(
+            &[("[U:1:1]", "Sniper99", "a.dem"), ("[U:1:2]", "sniper99", "a.dem")],
+            &AltReviews::default(),
+        );
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn rejected_pairs_are_never_linked() {
+        let players = [("[U:1:1]", "Sniper99", "a.dem"), ("[U:1:2]", "sniper99", "b.dem"), ("[U:1:3]", "sniper99", "c.dem")];
+        let mut reviews = AltReviews::default();
+        reviews.reject(&["[U:1:2]".to_string(), "[U:1:1]".to_string()]);
+        assert_eq!(reviews.rejected, [("[U:1:1]".to_string(), "[U:1:2]".to_string())]);
+
+        // Still clustered through the third account, but without the rejected link
+        let clusters = clusters_of(&players, &reviews);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].members, ["[U:1:1]", "[U:1:2]", "[U:1:3]"]);
+        assert!(clusters[0].links.iter().all(|link| (link.a.as_str(), link.b.as_str()) != ("[U:1:1]", "[U:1:2]")));
+
+        reviews.reject(&["[U:1:1]".to_string(), "[U:1:2]".to_string(), "[U:1:3]".to_string()]);
+        assert_eq!(reviews.rejected.len(), 3);
+        assert!(clusters_of(&players, &reviews).is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use anyhow::Error;
+use serde_json::json;
+use tf_demo_parser::{
+    demo::{data::DemoTick, message::Message},
+    MessageType, ParserState,
+};
+
+use super::{CheatAlgorithm, CheatAnalyserState, Detection, HitEvent};
+
+// Hits lining up with positions this many ticks old are backtracked
+const MIN_BACKTRACK_TICKS: u32 = 2;
+const MAX_BACKTRACK_TICKS: u32 = 12;
+// Eye height above the player origin while standing
+pub(super) const EYE_HEIGHT: f32 = 68.0;
+// Player hull, used as a vertical capsule around the origin
+pub(super) const HULL_RADIUS: f32 = 24.0;
+pub(super) const HULL_HEIGHT: f32 = 82.0;
+// Below this distance the aim vector says nothing useful
+const MIN_HIT_DISTANCE: f32 = 64.0;
+// Backtracked hits needed before a player is reported
+const MIN_BACKTRACK_HITS: usize = 3;
+// Share of evaluated hits that must be backtracked before a player is reported
+const MIN_BACKTRACK_RATIO: f32 = 0.2;
+
+// Weapons whose damage doesn't originate from the crosshair at the hit tick
+const NON_HITSCAN_WEAPONS: &[&str] = &[
+    "tf_projectile_rocket", "tf_projectile_pipe", "tf_projectile_pipe_remote",
+    "tf_projectile_arrow", "tf_projectile_healing_bolt", "tf_projectile_flare",
+    "flamethrower", "deflect_rocket", "deflect_promode", "deflect_sticky",
+    "world", "player", "obj_sentrygun", "obj_sentrygun2", "obj_sentrygun3",
+    "bleed_kill", "bat", "wrench", "fists", "club", "shovel", "bottle",
+    "fireaxe", "bonesaw", "knife", "sword", "taunt_pyro", "taunt_heavy",
+];
+
+/// A hit that only lines up with where the victim was several ticks ago
+struct BacktrackHit {
+    tick: u32,
+    victim: u64,
+    weapon: Option<String>,
+    best_offset: u32,
+    current_miss: f32,   // distance from the aim ray to the victim's current hull
+    backtrack_miss: f32, // distance from the aim ray to the backtracked hull
+    distance: f32,
+}
+
+/// Flags hits that only line up with a victim position 2-12 ticks old,
+/// the signature of cheats abusing lag compensation through backtracking.
+pub struct BacktrackAnalyzer {
+    // Recent positions per player, newest last
+    position_history: HashMap<u64, VecDeque<(u32, (f32, f32, f32))>>,
+    evaluated_hits: HashMap<u64, usize>,
+    backtrack_hits: HashMap<u64, Vec<BacktrackHit>>,
+    player_names: HashMap<u64, String>,
+}
+
+impl BacktrackAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            position_history: HashMap::new(),
+            evaluated_hits: HashMap::new(),
+            backtrack_hits: HashMap::new(),
+            player_names: HashMap::new(),
+        }
+    }
+
+    fn record_positions(&mut self, state: &CheatAnalyserState) {
+        for (player_id, player_state) in &state.player_states {
+            if let Some(position) = player_state.position {
+                let history = self.position_history.entry(*player_id).or_default();
+                if history.back().map_or(true, |(tick, _)| *tick < state.tick) {
+                    history.push_back((state.tick, position));
+                }
+                while history.len() > (MAX_BACKTRACK_TICKS + 2) as usize {
+                    history.pop_front();
+                }
+            }
+        }
+    }
+
+    fn check_hit(&mut self, hit: &HitEvent, state: &CheatAnalyserState) {
+        if hit.attacker == hit.victim {
+            return;
+        }
+        if let Some(weapon) = &hit.weapon {
+            if NON_HITSCAN_WEAPONS.contains(&weapon.as_str()) {
+                return;
+            }
+        }
+
+        let attacker = match state.player_states.get(&hit.attacker) {
+            Some(attacker) => attacker,
+            None => return,
+        };
+        let (pitch, yaw, origin) = match (attacker.viewangles, attacker.position) {
+            (Some((pitch, yaw, _)), Some(origin)) => (pitch, yaw, origin),
+            _ => return,
+        };
+        let history = match self.position_history.get(&hit.victim) {
+            Some(history) if !history.is_empty() => history,
+            _ => return,
+        };
+
+        let eye = (origin.0, origin.1, origin.2 + EYE_HEIGHT);
+        let aim = aim_direction(pitch, yaw);
+
+        // Miss distance for every recorded victim position, indexed by age in ticks
+        let mut misses: Vec<(u32, f32, f32)> = Vec::new(); // age, miss distance, distance to victim
+        for (tick, position) in history.iter().rev() {
+            let age = hit.tick.saturating_sub(*tick);
+            if age > MAX_BACKTRACK_TICKS {
+                break;
+            }
+            let center = (position.0, position.1, position.2 + HULL_HEIGHT / 2.0);
+            let distance = length(sub(center, eye));
+            misses.push((age, ray_hull_distance(eye, aim, *position), distance));
+        }
+
+        let current = misses.iter().filter(|(age, _, _)| *age < MIN_BACKTRACK_TICKS).min_by(|a, b| a.1.total_cmp(&b.1));
+        let backtracked = misses.iter().filter(|(age, _, _)| *age >= MIN_BACKTRACK_TICKS).min_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (current_miss, distance) = match current {
+            Some(&(_, current_miss, distance)) => (current_miss, distance),
+            None => return,
+        };
+        if distance < MIN_HIT_DISTANCE {
+            return;
+        }
+
+        *self.evaluated_hits.entry(hit.attacker).or_default() += 1;
+
+        if let Some(&(age, backtrack_miss, _)) = backtracked {
+            // The crosshair must miss the victim where they are now, but pass
+            // through their hull where they were a few ticks ago
+            if current_miss > HULL_RADIUS && backtrack_miss <= HULL_RADIUS {
+                self.backtrack_hits.entry(hit.attacker).or_default().push(BacktrackHit {
+                    tick: hit.tick,
+                    victim: hit.victim,
+                    weapon: hit.weapon.clone(),
+                    best_offset: age,
+                    current_miss,
+                    backtrack_miss,
+                    distance,
+                });
+            }
+        }
+    }
+}
+
+impl CheatAlgorithm for BacktrackAnalyzer {
+    fn algorithm_name(&self) -> &str {
+        "backtrack_analyzer"
+    }
+
+    fn handled_messages(&self) -> Result<Vec<MessageType>, bool> {
+        Ok(vec![MessageType::GameEvent])
+    }
+
+    fn on_tick(&mut self, state: &CheatAnalyserState, _parser_state: &ParserState) -> Result<Vec<Detection>, Error> {
+        for (player_id, player_state) in &state.player_states {
+            if !player_state.name.is_empty() {
+                self.player_names.insert(*player_id, player_state.name.clone());
+            }
+        }
+
+        self.record_positions(state);
+
+        // A kill usually arrives together with the hurt event of the same shot
+        let mut seen = HashSet::new();
+        for hit in &state.hit_events {
+            if seen.insert((hit.attacker, hit.victim, hit.tick)) {
+                self.check_hit(hit, state);
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    fn on_message(&mut self, _message: &Message, _state: &CheatAnalyserState, _parser_state: &ParserState, _tick: DemoTick) -> Result<Vec<Detection>, Error> {
+        Ok(vec![])
+    }
+
+    fn finish(&mut self) -> Result<Vec<Detection>, Error> {
+        let mut detections = Vec::new();
+
+        for (player_id, hits) in &self.backtrack_hits {
+            let evaluated = self.evaluated_hits.get(player_id).copied().unwrap_or(0).max(1);
+            let ratio = hits.len() as f32 / evaluated as f32;
+            if hits.len() < MIN_BACKTRACK_HITS || ratio < MIN_BACKTRACK_RATIO {
+                continue;
+            }
+
+            for hit in hits {
+                detections.push(Detection {
+                    tick: hit.tick,
+                    algorithm: self.algorithm_name().to_string(),
+                    player: *player_id,
+                    data: json!({
+                        "type": "backtrack",
+                        "player_name": self.player_names.get(player_id).cloned().unwrap_or_default(),
+                        "victim": hit.victim,
+                        "victim_name": self.player_names.get(&hit.victim).cloned().unwrap_or_default(),
+                        "weapon": hit.weapon,
+                        "backtrack_ticks": hit.best_offset,
+                        "current_miss": hit.current_miss,
+                        "backtrack_miss": hit.backtrack_miss,
+                        "distance": hit.distance,
+                        "backtracked_hits": hits.len(),
+                        "evaluated_hits": evaluated,
+                        "ratio": ratio,
+                    }),
+                    timestamp: None,
+                });
+            }
+        }
+
+        self.position_history.clear();
+        Ok(detections)
+    }
+}
+
+/// Unit vector the player is looking along, Source angles are in degrees
+/// with positive pitch looking down
+pub(super) fn aim_direction(pitch: f32, yaw: f32) -> (f32, f32, f32) {
+    let (pitch, yaw) = (pitch.to_radians(), yaw.to_radians());
+    (pitch.cos() * yaw.cos(), pitch.cos() * yaw.sin(), -pitch.sin())
+}
+
+pub(super) fn sub(a: (f32, f32, f32), b: (f32, f32, f32)) -> (f32, f32, f32) {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+pub(super) fn dot(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+pub(super) fn length(a: (f32, f32, f32)) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// Closest distance between the aim ray and the vertical axis of a player hull
+fn ray_hull_distance(eye: (f32, f32, f32), aim: (f32, f32, f32), origin: (f32, f32, f32)) -> f32 {
+    const SAMPLES: usize = 12;
+    (0..=SAMPLES)
+        .map(|i| {
+            let point = (origin.0, origin.1, origin.2 + HULL_HEIGHT * i as f32 / SAMPLES as f32);
+            let to_point = sub(point, eye);
+            let along = dot(to_point, aim);
+            if along <= 0.0 {
+                // Behind the shooter
+                return f32::MAX;
+            }
+            let closest = (eye.0 + aim.0 * along, eye.1 + aim.1 * along, eye.2 + aim.2 * along);
+            length(sub(point, closest))
+        })
+        .fold(f32::MAX, f32::min)
+}
@@ -1,21 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use anyhow::Error;
 use serde_json::json;
 use tf_demo_parser::{
     demo::{
+        gameevent_gen::GameEvent,
         header::Header,
+        message::Message,
         packet::{Packet, message::MessagePacket},
+        sendprop::SendPropIdentifier,
     },
     ParserState,
 };
 
-use crate::cheater_detection::{CheatAlgorithm, CheatAnalyserState, Detection, SILENT};
+use crate::cheater_detection::{playdemo_path, CheatAlgorithm, CheatAnalyserState, DemoKind, DemoTimestamp, Detection, HitEvent, PlayerState, DEFAULT_TICK_INTERVAL, SILENT};
+
+// TF_CUSTOM_HEADSHOT and TF_CUSTOM_HEADSHOT_DECAPITATION
+const CUSTOM_HEADSHOT: u16 = 1;
+const CUSTOM_HEADSHOT_DECAPITATION: u16 = 51;
+// The recorder of a POV demo is in the player list from the start, if nobody with the
+// header's name shows up this long after the first player it's a renamed SourceTV
+const RECORDER_GRACE_TICKS: u32 = 66;
+// Player conditions bitmask, bit 1 is TF_COND_ZOOMED while a sniper is scoped in
+const PLAYER_COND_PROP: SendPropIdentifier = SendPropIdentifier::new("DT_TFPlayerShared", "m_nPlayerCond");
+const TF_COND_ZOOMED: i64 = 1 << 1;
 
 pub struct CheatAnalyser {
     algorithms: Vec<Box<dyn CheatAlgorithm>>,
     state: CheatAnalyserState,
     detections: Vec<Detection>,
+    skipped_algorithms: Vec<String>, // not supported for this demo kind
     tick_count: u32,
+    playdemo_path: Option<String>,
+    recorder_name: Option<String>, // header nick, matched against the player list
+    first_player_tick: Option<u32>,
+    zoomed: HashMap<u32, bool>, // entity index -> scoped in
 }
 
 impl CheatAnalyser {
@@ -24,10 +42,20 @@ impl CheatAnalyser {
             algorithms,
             state: CheatAnalyserState {
                 tick: 0,
+                demo_kind: DemoKind::Unknown,
+                tick_interval: DEFAULT_TICK_INTERVAL,
                 player_states: HashMap::new(),
+                hit_events: Vec::new(),
+                map: None,
+                usercmd_players: HashSet::new(),
             },
             detections: Vec::new(),
+            skipped_algorithms: Vec::new(),
             tick_count: 0,
+            playdemo_path: None,
+            recorder_name: None,
+            first_player_tick: None,
+            zoomed: HashMap::new(),
         }
     }
 
@@ -38,10 +66,76 @@ impl CheatAnalyser {
         Ok(())
     }
 
+    /// Take the demo kind and tick rate from the demo header
+    pub fn handle_header(&mut self, header: &Header) {
+        if header.ticks > 0 && header.duration > 0.0 {
+            self.state.tick_interval = header.duration / header.ticks as f32;
+        }
+        self.recorder_name = Some(header.nick.clone()).filter(|nick| !nick.is_empty());
+        self.set_demo_kind(DemoKind::from_header(header));
+    }
+
+    /// Map geometry for algorithms that check line of sight
+    pub fn set_map(&mut self, map: Option<std::sync::Arc<super::bsp::BspMap>>) {
+        self.state.map = map;
+    }
+
+    /// Demo file the detections belong to, used for the playdemo commands
+    pub fn set_demo_path(&mut self, path: &std::path::Path) {
+        self.playdemo_path = Some(playdemo_path(path));
+    }
+
+    /// Demo time and console command for a tick, None when the demo file isn't known
+    pub fn timestamp_for(&self, tick: u32) -> Option<DemoTimestamp> {
+        self.playdemo_path.as_ref()
+            .map(|path| DemoTimestamp::new(tick, self.state.tick_interval, path))
+    }
+
+    /// Record who recorded the demo and drop algorithms that can't work with it
+    pub fn set_demo_kind(&mut self, kind: DemoKind) {
+        self.state.demo_kind = kind;
+
+        let (supported, skipped): (Vec<_>, Vec<_>) = std::mem::take(&mut self.algorithms)
+            .into_iter()
+            .partition(|algorithm| algorithm.supports_demo_kind(kind));
+        self.algorithms = supported;
+
+        for algorithm in skipped {
+            if !SILENT.load(std::sync::atomic::Ordering::Relaxed) {
+                println!("Skipping {} for {:?} demo", algorithm.algorithm_name(), kind);
+            }
+            self.skipped_algorithms.push(algorithm.algorithm_name().to_string());
+        }
+    }
+
+    pub fn get_demo_kind(&self) -> DemoKind {
+        self.state.demo_kind
+    }
+
+    /// Take a player's angles as full precision usercmds, for the recorder of a POV demo
+    /// or traces that were never networked
+    pub fn set_usercmd_player(&mut self, steamid: u64) {
+        self.state.usercmd_players.insert(steamid);
+    }
+
+    pub fn get_tick_interval(&self) -> f32 {
+        self.state.tick_interval
+    }
+
+    pub fn get_usercmd_players(&self) -> &HashSet<u64> {
+        &self.state.usercmd_players
+    }
+
+    pub fn get_skipped_algorithms(&self) -> &Vec<String> {
+        &self.skipped_algorithms
+    }
+
     pub fn handle_message(&mut self, message: &MessagePacket, parser_state: &ParserState, tick: u32) -> Result<(), Error> {
         self.tick_count = tick;
         self.state.tick = tick;
         
+        self.queue_hit_events(message, tick);
+        
         for algorithm in &mut self.algorithms {
             // Always process all messages for simplicity
             if !message.messages.is_empty() {
@@ -50,17 +144,125 @@ impl CheatAnalyser {
                 self.detections.extend(detections);
             }
         }
+        
+        // Every algorithm has seen this packet's events now
+        self.state.hit_events.clear();
         Ok(())
     }
+    
+    /// Resolve the damage and kill events of a packet, the algorithms get them with the
+    /// following `on_tick` round (right away from `handle_message`, otherwise the next
+    /// `handle_tick_at`) and they are cleared after it
+    pub fn queue_hit_events(&mut self, message: &MessagePacket, tick: u32) {
+        for message in &message.messages {
+            if let Message::GameEvent(event) = message {
+                if let Some(hit) = self.resolve_hit_event(&event.event, tick) {
+                    self.state.hit_events.push(hit);
+                }
+            }
+        }
+    }
+    
+    /// Follow the zoom condition of the player entities, the game state analyser doesn't track conditions
+    pub fn track_conditions(&mut self, message: &MessagePacket, parser_state: &ParserState) {
+        for message in &message.messages {
+            if let Message::PacketEntities(entities) = message {
+                for entity in &entities.entities {
+                    let cond = entity.get_prop_by_identifier(&PLAYER_COND_PROP, parser_state)
+                        .and_then(|prop| i64::try_from(&prop.value).ok());
+                    if let Some(cond) = cond {
+                        self.zoomed.insert(u32::from(entity.entity_index), cond & TF_COND_ZOOMED != 0);
+                    }
+                }
+                for entity in &entities.removed_entities {
+                    self.zoomed.remove(&u32::from(*entity));
+                }
+            }
+        }
+    }
+
+    /// Whether a player entity is scoped in, None until its conditions were networked
+    pub fn is_zoomed(&self, entity: u32) -> Option<bool> {
+        self.zoomed.get(&entity).copied()
+    }
+
+    fn steamid_for_user(&self, user_id: u16) -> Option<u64> {
+        self.state.player_states.values()
+            .find(|player| player.user_id == Some(user_id))
+            .map(|player| player.steamid)
+    }
+    
+    fn resolve_hit_event(&self, event: &GameEvent, tick: u32) -> Option<HitEvent> {
+        match event {
+            GameEvent::PlayerHurt(hurt) => Some(HitEvent {
+                tick,
+                attacker: self.steamid_for_user(hurt.attacker)?,
+                victim: self.steamid_for_user(hurt.user_id)?,
+                weapon: None,
+                damage: hurt.damage_amount,
+                headshot: hurt.custom == CUSTOM_HEADSHOT || hurt.custom == CUSTOM_HEADSHOT_DECAPITATION,
+                kill: false,
+            }),
+            GameEvent::PlayerDeath(death) => Some(HitEvent {
+                tick,
+                attacker: self.steamid_for_user(death.attacker)?,
+                victim: self.steamid_for_user(death.user_id)?,
+                weapon: Some(death.weapon.to_string()),
+                damage: 0,
+                headshot: death.custom_kill == CUSTOM_HEADSHOT || death.custom_kill == CUSTOM_HEADSHOT_DECAPITATION,
+                kill: true,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Replace the tracked state of a player before the next tick is handled
+    pub fn update_player_state(&mut self, player_state: PlayerState) {
+        // Only the recorder's own angles in a POV demo come from usercmds
+        if self.state.demo_kind == DemoKind::Pov
+            && self.state.usercmd_players.is_empty()
+            && self.recorder_name.as_deref() == Some(player_state.name.as_str())
+        {
+            self.state.usercmd_players.insert(player_state.steamid);
+        }
+        self.state.player_states.insert(player_state.steamid, player_state);
+    }
+
+    /// A POV demo whose recorder never joined as a player was recorded by a renamed SourceTV
+    fn check_recorder(&mut self, tick: u32) {
+        if self.state.demo_kind != DemoKind::Pov
+            || self.recorder_name.is_none()
+            || !self.state.usercmd_players.is_empty()
+            || self.state.player_states.is_empty()
+        {
+            return;
+        }
+        let first = *self.first_player_tick.get_or_insert(tick);
+        if tick.saturating_sub(first) > RECORDER_GRACE_TICKS {
+            self.set_demo_kind(DemoKind::SourceTv);
+        }
+    }
+
+    /// Queue a hit for the next tick, for callers replaying events outside of demo packets
+    pub fn add_hit_event(&mut self, hit: HitEvent) {
+        self.state.hit_events.push(hit);
+    }
 
     pub fn handle_tick(&mut self, parser_state: &ParserState) -> Result<(), Error> {
-        self.tick_count += 1;
-        self.state.tick = self.tick_count;
+        self.handle_tick_at(self.tick_count + 1, parser_state)
+    }
+
+    /// Run the algorithms for the given demo tick
+    pub fn handle_tick_at(&mut self, tick: u32, parser_state: &ParserState) -> Result<(), Error> {
+        self.tick_count = tick;
+        self.state.tick = tick;
+        self.check_recorder(tick);
 
         for algorithm in &mut self.algorithms {
             let detections = algorithm.on_tick(&self.state, parser_state)?;
             self.detections.extend(detections);
         }
+        self.state.hit_events.clear();
         Ok(())
     }
 
@@ -69,6 +271,20 @@ impl CheatAnalyser {
             let detections = algorithm.finish()?;
             self.detections.extend(detections);
         }
+
+        let timestamps: Vec<_> = self.detections.iter()
+            .map(|detection| self.timestamp_for(detection.tick))
+            .collect();
+        for (detection, timestamp) in self.detections.iter_mut().zip(timestamps) {
+            if detection.timestamp.is_none() {
+                detection.timestamp = timestamp;
+            }
+            // Names can have spaces, the VDM export spectates suspects by entity index
+            let entity = self.state.player_states.get(&detection.player).and_then(|player| player.entity);
+            if let (Some(entity), Some(data)) = (entity, detection.data.as_object_mut()) {
+                data.entry("entity").or_insert(json!(entity));
+            }
+        }
         Ok(())
     }
 
@@ -77,6 +293,9 @@ impl CheatAnalyser {
             "detections": self.detections,
             "metadata": {
                 "total_ticks": self.tick_count,
+                "tick_interval": self.state.tick_interval,
+                "demo_kind": self.state.demo_kind,
+                "skipped_algorithms": self.skipped_algorithms,
             }
         });
 
@@ -115,20 +334,19 @@ impl CheatDemoHandler {
         }
     }
 
-    pub fn handle_header(&mut self, _header: &Header) {
-        // Just record that we've processed a header
+    pub fn handle_header(&mut self, header: &Header) {
+        self.analyser.handle_header(header);
     }
 
     pub fn handle_packet(&mut self, packet: Packet) -> Result<(), Error> {
+        // Every packet carries the demo tick it was recorded at
+        let tick = u32::from(packet.tick());
         match packet {
             Packet::Message(message) => {
-                // Use the current tick count
-                let tick = self.analyser.get_tick_count_u32();
                 self.analyser.handle_message(&message, &self.state_handler, tick)?;
             }
             Packet::Signon(_) | Packet::SyncTick(_) => {
-                // Handle sync packets
-                self.analyser.handle_tick(&self.state_handler)?;
+                self.analyser.handle_tick_at(tick, &self.state_handler)?;
             }
             _ => {}
         }
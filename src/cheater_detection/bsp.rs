@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Folder next to the settings file where maps can be dropped when TF2 isn't installed
+pub const MAP_CACHE_DIR: &str = "map_cache";
+
+const LUMP_PLANES: usize = 1;
+const LUMP_NODES: usize = 5;
+const LUMP_LEAFS: usize = 10;
+const HEADER_LUMPS: usize = 64;
+
+const PLANE_SIZE: usize = 20;
+const NODE_SIZE: usize = 32;
+// Leaves carry ambient lighting up to BSP version 19
+const LEAF_SIZE: usize = 32;
+const LEAF_SIZE_V19: usize = 56;
+
+// Contents that block sight, grates and windows can be seen through
+const CONTENTS_SOLID: i32 = 0x1;
+const CONTENTS_OPAQUE: i32 = 0x80;
+const BLOCKS_SIGHT: i32 = CONTENTS_SOLID | CONTENTS_OPAQUE;
+
+// Splits closer than this to a plane are treated as on the plane
+const PLANE_EPSILON: f32 = 0.03125;
+
+struct Plane {
+    normal: (f32, f32, f32),
+    dist: f32,
+}
+
+struct Node {
+    plane: usize,
+    children: [i32; 2], // negative children are leaves, -1 - index
+}
+
+/// BSP tree of a map, enough to trace lines of sight against world brushes.
+/// Displacements, props and func_ brush entities aren't part of it.
+pub struct BspMap {
+    pub name: String,
+    planes: Vec<Plane>,
+    nodes: Vec<Node>,
+    leaf_contents: Vec<i32>,
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read_f32(data: &[u8], offset: usize) -> Option<f32> {
+    Some(f32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Bytes of one lump, along with its version
+fn lump(data: &[u8], index: usize) -> Result<(&[u8], i32), String> {
+    let entry = 8 + index * 16;
+    let (offset, length, version) = match (read_i32(data, entry), read_i32(data, entry + 4), read_i32(data, entry + 8)) {
+        (Some(offset), Some(length), Some(version)) => (offset as usize, length as usize, version),
+        _ => return Err("Truncated BSP header".to_string()),
+    };
+    let bytes = offset.checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| format!("Lump {} points outside the file", index))?;
+    if bytes.starts_with(b"LZMA") {
+        return Err(format!("Lump {} is LZMA compressed, which isn't supported", index));
+    }
+    Ok((bytes, version))
+}
+
+impl BspMap {
+    pub fn parse(name: &str, data: &[u8]) -> Result<Self, String> {
+        if data.get(0..4) != Some(b"VBSP".as_slice()) {
+            return Err("Not a Source BSP file".to_string());
+        }
+        let version = read_i32(data, 4).ok_or("Truncated BSP header")?;
+        if data.len() < 8 + HEADER_LUMPS * 16 {
+            return Err("Truncated BSP header".to_string());
+        }
+
+        let (plane_bytes, _) = lump(data, LUMP_PLANES)?;
+        let planes = plane_bytes.chunks_exact(PLANE_SIZE)
+            .map(|chunk| Plane {
+                normal: (
+                    read_f32(chunk, 0).unwrap_or_default(),
+                    read_f32(chunk, 4).unwrap_or_default(),
+                    read_f32(chunk, 8).unwrap_or_default(),
+                ),
+                dist: read_f32(chunk, 12).unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+
+        let (node_bytes, _) = lump(data, LUMP_NODES)?;
+        let nodes = node_bytes.chunks_exact(NODE_SIZE)
+            .map(|chunk| Node {
+                plane: read_i32(chunk, 0).unwrap_or_default().max(0) as usize,
+                children: [read_i32(chunk, 4).unwrap_or(-1), read_i32(chunk, 8).unwrap_or(-1)],
+            })
+            .collect::<Vec<_>>();
+
+        let (leaf_bytes, leaf_version) = lump(data, LUMP_LEAFS)?;
+        let leaf_size = if version <= 19 && leaf_version == 0 { LEAF_SIZE_V19 } else { LEAF_SIZE };
+        let leaf_contents = leaf_bytes.chunks_exact(leaf_size)
+            .map(|chunk| read_i32(chunk, 0).unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        if nodes.is_empty() || leaf_contents.is_empty() {
+            return Err("BSP has no nodes or leaves".to_string());
+        }
+        if nodes.iter().any(|node| node.plane >= planes.len()) {
+            return Err("BSP node references a missing plane".to_string());
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            planes,
+            nodes,
+            leaf_contents,
+        })
+    }
+
+    pub fn load(name: &str, path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(name, &data)
+    }
+
+    /// True when nothing solid in the world lies between the two points
+    pub fn is_visible(&self, from: (f32, f32, f32), to: (f32, f32, f32)) -> bool {
+        !self.segment_blocked(0, from, to, 0)
+    }
+
+    fn segment_blocked(&self, node: i32, start: (f32, f32, f32), end: (f32, f32, f32), depth: usize) -> bool {
+        // A corrupt tree can't recurse forever
+        if depth > self.nodes.len() {
+            return false;
+        }
+        if node < 0 {
+            let leaf = (-1 - node) as usize;
+            return self.leaf_contents.get(leaf).map_or(false, |contents| contents & BLOCKS_SIGHT != 0);
+        }
+
+        let node_data = match self.nodes.get(node as usize) {
+            Some(node_data) => node_data,
+            None => return false,
+        };
+        let plane = &self.planes[node_data.plane];
+        let side = |point: (f32, f32, f32)| {
+            plane.normal.0 * point.0 + plane.normal.1 * point.1 + plane.normal.2 * point.2 - plane.dist
+        };
+        let (start_side, end_side) = (side(start), side(end));
+
+        if start_side >= -PLANE_EPSILON && end_side >= -PLANE_EPSILON {
+            return self.segment_blocked(node_data.children[0], start, end, depth + 1);
+        }
+        if start_side < PLANE_EPSILON && end_side < PLANE_EPSILON {
+            return self.segment_blocked(node_data.children[1], start, end, depth + 1);
+        }
+
+        // The segment crosses the plane, check the near half first
+        let fraction = (start_side / (start_side - end_side)).clamp(0.0, 1.0);
+        let middle = (
+            start.0 + (end.0 - start.0) * fraction,
+            start.1 + (end.1 - start.1) * fraction,
+            start.2 + (end.2 - start.2) * fraction,
+        );
+        let (near, far) = if start_side >= 0.0 { (0, 1) } else { (1, 0) };
+        self.segment_blocked(node_data.children[near], start, middle, depth + 1)
+            || self.segment_blocked(node_data.children[far], middle, end, depth + 1)
+    }
+}
+
+/// Folders a map is looked for in: the TF2 install the demo lives in, the
+/// configured TF2 folder, and the local map cache
+pub fn map_search_dirs(demo_path: &Path, tf2_folder: Option<&Path>) -> Vec<PathBuf> {
+    let mut tf_dirs = Vec::new();
+    // Demos are usually recorded into tf/ or tf/demos/
+    if let Some(tf_dir) = demo_path.ancestors().find(|dir| dir.file_name().map_or(false, |name| name == "tf")) {
+        tf_dirs.push(tf_dir.to_path_buf());
+    }
+    if let Some(tf2_folder) = tf2_folder {
+        tf_dirs.push(tf2_folder.join("tf"));
+    }
+
+    let mut dirs = Vec::new();
+    for tf_dir in tf_dirs {
+        for sub_dir in ["maps", "download/maps"] {
+            let dir = tf_dir.join(sub_dir);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs.push(PathBuf::from(MAP_CACHE_DIR));
+    dirs
+}
+
+/// Maps already loaded, demos on the same map share one parsed tree
+fn loaded_maps() -> &'static Mutex<HashMap<String, Arc<BspMap>>> {
+    static LOADED: OnceLock<Mutex<HashMap<String, Arc<BspMap>>>> = OnceLock::new();
+    LOADED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Find and load a map by the name in the demo header, None if it isn't available anywhere
+pub fn find_map(name: &str, search_dirs: &[PathBuf]) -> Result<Option<Arc<BspMap>>, String> {
+    // Map names come from the demo, don't let them walk out of the maps folder
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Ok(None);
+    }
+    if let Some(map) = loaded_maps().lock().unwrap().get(name) {
+        return Ok(Some(map.clone()));
+    }
+
+    let path = match search_dirs.iter().map(|dir| dir.join(format!("{}.bsp", name))).find(|path| path.exists()) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let map = Arc::new(BspMap::load(name, &path)?);
+    loaded_maps().lock().unwrap().insert(name.to_string(), map.clone());
+    Ok(Some(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BSP with one solid slab between x = -16 and x = 16, leaves are `leaf_size` bytes
+    fn wall_bsp(version: i32, leaf_size: usize) -> Vec<u8> {
+        let mut planes = Vec::new();
+        for dist in [-16.0f32, 16.0] {
+            for value in [1.0f32, 0.0, 0.0, dist] {
+                planes.extend(value.to_le_bytes());
+            }
+            planes.extend(0i32.to_le_bytes()); // type
+        }
+        // Node 0 splits at x = -16, its back is open. Node 1 splits at x = 16,
+        // in front is open and behind (inside the slab) is solid
+        let mut nodes = Vec::new();
+        for (plane, front, back) in [(0i32, 1i32, -1i32), (1, -1, -2)] {
+            for value in [plane, front, back] {
+                nodes.extend(value.to_le_bytes());
+            }
+            nodes.resize(nodes.len() + NODE_SIZE - 12, 0);
+        }
+        let mut leafs = Vec::new();
+        for contents in [0i32, CONTENTS_SOLID] {
+            leafs.extend(contents.to_le_bytes());
+            leafs.resize(leafs.len() + leaf_size - 4, 0);
+        }
+
+        let header_size = 8 + HEADER_LUMPS * 16 + 4;
+        let mut header = b"VBSP".to_vec();
+        header.extend(version.to_le_bytes());
+        let mut body: Vec<u8> = Vec::new();
+        for index in 0..HEADER_LUMPS {
+            let data: &[u8] = match index {
+                LUMP_PLANES => &planes,
+                LUMP_NODES => &nodes,
+                LUMP_LEAFS => &leafs,
+                _ => &[],
+            };
+            header.extend(((header_size + body.len()) as i32).to_le_bytes());
+            header.extend((data.len() as i32).to_le_bytes());
+            header.extend(0i32.to_le_bytes()); // lump version
+            header.extend([0u8; 4]);
+            body.extend(data);
+        }
+        header.extend(1i32.to_le_bytes()); // map revision
+        header.extend(body);
+        header
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bsp_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parses_lumps() {
+        let map = BspMap::parse("wall", &wall_bsp(20, LEAF_SIZE)).unwrap();
+        assert_eq!(map.planes.len(), 2);
+        assert_eq!(map.planes[1].normal, (1.0, 0.0, 0.0));
+        assert_eq!(map.planes[1].dist, 16.0);
+        assert_eq!(map.nodes.len(), 2);
+        assert_eq!(map.nodes[1].children, [-1, -2]);
+        assert_eq!(map.leaf_contents, [0, CONTENTS_SOLID]);
+
+        // Version 19 leaves carry ambient lighting
+        let map = BspMap::parse("wall", &wall_bsp(19, LEAF_SIZE_V19)).unwrap();
+        assert_eq!(map.leaf_contents, [0, CONTENTS_SOLID]);
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert!(BspMap::parse("x", b"IBSP").is_err());
+        let data = wall_bsp(20, LEAF_SIZE);
+        assert!(BspMap::parse("x", &data[..100]).is_err());
+
+        // Planes lump running past the end of the file
+        let mut outside = data.clone();
+        let entry = 8 + LUMP_PLANES * 16 + 4;
+        outside[entry..entry + 4].copy_from_slice(&(data.len() as i32).to_le_bytes());
+        assert!(BspMap::parse("x", &outside).is_err());
+
+        let mut compressed = data.clone();
+        let offset = read_i32(&data, 8 + LUMP_NODES * 16).unwrap() as usize;
+        compressed[offset..offset + 4].copy_from_slice(b"LZMA");
+        assert!(BspMap::parse("x", &compressed).err().unwrap().contains("LZMA"));
+    }
+
+    #[test]
+    fn traces_through_the_wall() {
+        let map = BspMap::parse("wall", &wall_bsp(20, LEAF_SIZE)).unwrap();
+        assert!(!map.is_visible((-200.0, 0.0, 64.0), (200.0, 50.0, 64.0)));
+        assert!(!map.is_visible((200.0, 0.0, 0.0), (-200.0, 0.0, 0.0)));
+        assert!(map.is_visible((-200.0, 0.0, 64.0), (-20.0, 300.0, 0.0)));
+        assert!(map.is_visible((20.0, 0.0, 0.0), (500.0, -100.0, 0.0)));
+        // Running along the face of the wall doesn't enter it
+        assert!(map.is_visible((16.0, -100.0, 0.0), (16.0, 100.0, 0.0)));
+    }
+
+    #[test]
+    fn search_dirs_in_order() {
+        let demo = Path::new("steam/tf/demos/match.dem");
+        let dirs = map_search_dirs(demo, Some(Path::new("other")));
+        let expected: Vec<PathBuf> = [
+            "steam/tf/maps",
+            "steam/tf/download/maps",
+            "other/tf/maps",
+            "other/tf/download/maps",
+            MAP_CACHE_DIR,
+        ].iter().map(PathBuf::from).collect();
+        assert_eq!(dirs, expected);
+
+        // The configured folder is the same install, listed once
+        let dirs = map_search_dirs(demo, Some(Path::new("steam")));
+        assert_eq!(dirs.len(), 3);
+        assert_eq!(map_search_dirs(Path::new("match.dem"), None), [PathBuf::from(MAP_CACHE_DIR)]);
+    }
+
+    #[test]
+    fn find_map_takes_the_first_match() {
+        let (first, second) = (temp_dir("first"), temp_dir("second"));
+        // Only the later folder has a usable copy, the earlier one wins anyway
+        std::fs::write(first.join("ctf_order_test.bsp"), b"not a bsp").unwrap();
+        std::fs::write(second.join("ctf_order_test.bsp"), wall_bsp(20, LEAF_SIZE)).unwrap();
+        std::fs::write(second.join("ctf_fallback_test.bsp"), wall_bsp(20, LEAF_SIZE)).unwrap();
+        let dirs = vec![temp_dir("missing"), first.clone(), second.clone()];
+
+        assert!(find_map("ctf_order_test", &dirs).is_err());
+        let map = find_map("ctf_fallback_test", &dirs).unwrap().unwrap();
+        assert_eq!(map.name, "ctf_fallback_test");
+        // Loaded once and shared afterwards, even once the file is gone
+        std::fs::remove_file(second.join("ctf_fallback_test.bsp")).unwrap();
+        assert!(Arc::ptr_eq(&map, &find_map("ctf_fallback_test", &dirs).unwrap().unwrap()));
+
+        assert!(find_map("ctf_nowhere_test", &dirs).unwrap().is_none());
+        assert!(find_map("../second/ctf_order_test", &dirs).unwrap().is_none());
+        assert!(find_map("", &dirs).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(first);
+        let _ = std::fs::remove_dir_all(second);
+    }
+}
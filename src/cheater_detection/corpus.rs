@@ -0,0 +1,519 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::ParserState;
+
+use super::{
+    all_algorithms, base::CheatAnalyser, bsp::BspMap, demo, viewangles::ViewAnglesAnalyzer,
+    CheatAlgorithm, DemoKind, Detection, HitEvent, PlayerState,
+};
+
+/// Where the labelled fixtures live, relative to the repository root
+pub const CORPUS_DIR: &str = "fixtures/cheat_corpus";
+
+// Ticks a detection may be off from its label and still count
+const DEFAULT_TICK_TOLERANCE: u32 = 8;
+
+/// Ground truth for one fixture, stored as `labels.json` next to the trace
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Labels {
+    pub description: String,
+    // Demo fixtures take both from their header
+    #[serde(default)]
+    pub demo_kind: Option<DemoKind>,
+    // Recorder of a POV fixture, without one every player's angles count as usercmds
+    #[serde(default)]
+    pub recorder: Option<u64>,
+    // Algorithms graded on this fixture, detections from the rest are ignored
+    pub algorithms: Vec<String>,
+    #[serde(default = "default_tolerance")]
+    pub tolerance: u32,
+    pub expected: Vec<ExpectedDetection>,
+}
+
+fn default_tolerance() -> u32 {
+    DEFAULT_TICK_TOLERANCE
+}
+
+/// A detection the algorithm has to produce
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExpectedDetection {
+    pub algorithm: String,
+    pub player: u64,
+    pub tick: u32,
+    #[serde(default)]
+    pub tick_end: Option<u32>, // for detections repeated over a range of ticks
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+}
+
+impl ExpectedDetection {
+    fn matches(&self, detection: &Detection, tolerance: u32) -> bool {
+        let start = self.tick.saturating_sub(tolerance);
+        let end = self.tick_end.unwrap_or(self.tick) + tolerance;
+        let kind_matches = match &self.kind {
+            Some(kind) => detection.data.get("type").and_then(|t| t.as_str()) == Some(kind.as_str()),
+            None => true,
+        };
+        detection.algorithm == self.algorithm
+            && detection.player == self.player
+            && detection.tick >= start
+            && detection.tick <= end
+            && kind_matches
+    }
+}
+
+/// One row of a trace, same columns as the viewangle CSV plus optional class/zoom/team state
+#[derive(Deserialize)]
+struct TraceRow {
+    tick: u32,
+    player_id: u64,
+    player_name: String,
+    origin_x: f32,
+    origin_y: f32,
+    origin_z: f32,
+    viewangle: f32,
+    pitchangle: f32,
+    #[serde(default)]
+    class: Option<u8>,
+    #[serde(default)]
+    scoped: Option<bool>,
+    #[serde(default)]
+    team: Option<u8>,
+}
+
+/// One row of `events.csv`, a hit or kill replayed into the analyser
+#[derive(Deserialize)]
+struct EventRow {
+    tick: u32,
+    attacker: u64,
+    victim: u64,
+    #[serde(default)]
+    weapon: Option<String>,
+    damage: u16,
+    headshot: bool,
+    kill: bool,
+}
+
+/// What a fixture replays
+pub enum FixtureSource {
+    Trace(PathBuf), // `trace.csv` of player states
+    Demo(PathBuf),  // `demo.dem`, parsed the way the Demo Checker does
+}
+
+pub struct Fixture {
+    pub name: String,
+    pub source: FixtureSource,
+    pub events: Option<PathBuf>, // only replayed with a trace, demos have their own
+    pub map: Option<PathBuf>, // `map.bsp` for the line of sight checks
+    pub labels: Labels,
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct Score {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl Score {
+    pub fn precision(&self) -> f32 {
+        let found = self.true_positives + self.false_positives;
+        if found == 0 {
+            1.0
+        } else {
+            self.true_positives as f32 / found as f32
+        }
+    }
+
+    pub fn recall(&self) -> f32 {
+        let expected = self.true_positives + self.false_negatives;
+        if expected == 0 {
+            1.0
+        } else {
+            self.true_positives as f32 / expected as f32
+        }
+    }
+
+    fn add(&mut self, other: &Score) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.false_negatives += other.false_negatives;
+    }
+}
+
+pub struct FixtureReport {
+    pub name: String,
+    pub scores: BTreeMap<String, Score>,
+    pub missed: Vec<ExpectedDetection>,
+    pub unexpected: Vec<Detection>,
+}
+
+impl FixtureReport {
+    pub fn passed(&self) -> bool {
+        self.missed.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Every algorithm the corpus grades, including the viewangle checks the
+/// Demo Checker runs separately
+pub fn corpus_algorithms() -> Vec<Box<dyn CheatAlgorithm>> {
+    // Fixtures are graded in parallel, a shared output file would be written by all of them
+    let mut viewangles = ViewAnglesAnalyzer::new();
+    viewangles.set_write_output(false);
+
+    let mut algorithms = all_algorithms();
+    algorithms.push(Box::new(viewangles));
+    algorithms
+}
+
+/// Load every fixture directory that has a `trace.csv` or `demo.dem` and `labels.json`
+pub fn load_corpus(dir: &Path) -> Result<Vec<Fixture>, Error> {
+    let mut fixtures = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let labels_path = path.join("labels.json");
+        let source = if path.join("trace.csv").exists() {
+            FixtureSource::Trace(path.join("trace.csv"))
+        } else if path.join("demo.dem").exists() {
+            FixtureSource::Demo(path.join("demo.dem"))
+        } else {
+            continue;
+        };
+        if !labels_path.exists() {
+            continue;
+        }
+
+        let labels: Labels = serde_json::from_str(&std::fs::read_to_string(&labels_path)?)
+            .map_err(|e| anyhow!("{}: {}", labels_path.display(), e))?;
+        let events = Some(path.join("events.csv")).filter(|events| events.exists());
+        let map = Some(path.join("map.bsp")).filter(|map| map.exists());
+
+        fixtures.push(Fixture {
+            name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            source,
+            events,
+            map,
+            labels,
+        });
+    }
+
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+/// Replay a fixture through `CheatAnalyser` and return everything it detected
+pub fn run_fixture(fixture: &Fixture, algorithms: Vec<Box<dyn CheatAlgorithm>>) -> Result<Vec<Detection>, Error> {
+    run_fixture_every(fixture, algorithms, 1)
+}
+
+/// Replay only every `stride`th tick of a fixture, like SourceTV recording at a lower
+/// snapshot rate. Events arrive with the next recorded tick
+pub fn run_fixture_every(fixture: &Fixture, algorithms: Vec<Box<dyn CheatAlgorithm>>, stride: u32) -> Result<Vec<Detection>, Error> {
+    let trace = match &fixture.source {
+        FixtureSource::Trace(trace) => trace,
+        FixtureSource::Demo(demo) if stride == 1 => return run_demo(fixture, demo, algorithms),
+        FixtureSource::Demo(_) => return Err(anyhow!("{} is a demo, its snapshot rate is fixed", fixture.name)),
+    };
+
+    let mut rows: BTreeMap<u32, Vec<TraceRow>> = BTreeMap::new();
+    for row in csv::Reader::from_path(trace)?.deserialize() {
+        let row: TraceRow = row?;
+        if row.tick % stride == 0 {
+            rows.entry(row.tick).or_default().push(row);
+        }
+    }
+
+    let mut events: HashMap<u32, Vec<HitEvent>> = HashMap::new();
+    if let Some(path) = &fixture.events {
+        for row in csv::Reader::from_path(path)?.deserialize() {
+            let row: EventRow = row?;
+            let tick = row.tick.div_ceil(stride) * stride;
+            events.entry(tick).or_default().push(HitEvent {
+                tick,
+                attacker: row.attacker,
+                victim: row.victim,
+                weapon: row.weapon.filter(|weapon| !weapon.is_empty()),
+                damage: row.damage,
+                headshot: row.headshot,
+                kill: row.kill,
+            });
+        }
+    }
+
+    let mut analyser = CheatAnalyser::new(algorithms);
+    let parser_state = ParserState::new(0, |_| true, false);
+    analyser.init()?;
+    analyser.set_map(load_map(fixture)?);
+    analyser.set_demo_kind(fixture.labels.demo_kind.unwrap_or(DemoKind::Pov));
+    // Generated traces are never quantised
+    if analyser.get_demo_kind() == DemoKind::Pov {
+        match fixture.labels.recorder {
+            Some(recorder) => analyser.set_usercmd_player(recorder),
+            None => rows.values().flatten().for_each(|row| analyser.set_usercmd_player(row.player_id)),
+        }
+    }
+
+    for (tick, tick_rows) in rows {
+        for row in tick_rows {
+            analyser.update_player_state(PlayerState {
+                steamid: row.player_id,
+                user_id: None,
+                entity: None,
+                viewangles: Some((row.pitchangle, row.viewangle, 0.0)),
+                position: Some((row.origin_x, row.origin_y, row.origin_z)),
+                name: row.player_name,
+                class: row.class,
+                team: row.team,
+                scoped: row.scoped,
+            });
+        }
+        for hit in events.remove(&tick).unwrap_or_default() {
+            analyser.add_hit_event(hit);
+        }
+        analyser.handle_tick_at(tick, &parser_state)?;
+    }
+
+    analyser.finish()?;
+    Ok(analyser.get_detections().clone())
+}
+
+/// Parse a demo fixture through the same replay as the Demo Checker
+fn run_demo(fixture: &Fixture, demo: &Path, algorithms: Vec<Box<dyn CheatAlgorithm>>) -> Result<Vec<Detection>, Error> {
+    let bytes = std::fs::read(demo)?;
+
+    let mut analyser = CheatAnalyser::new(algorithms);
+    analyser.init()?;
+    analyser.set_map(load_map(fixture)?);
+    demo::replay_demo(&bytes, demo, &[], &mut analyser, &mut |_, _, _, _| {}, &|_| {}, &|_| {})
+        .map_err(|e| anyhow!("{}: {}", demo.display(), e))?;
+
+    analyser.finish()?;
+    Ok(analyser.get_detections().clone())
+}
+
+fn load_map(fixture: &Fixture) -> Result<Option<Arc<BspMap>>, Error> {
+    match &fixture.map {
+        Some(path) => {
+            let map = BspMap::load(&fixture.name, path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+            Ok(Some(Arc::new(map)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Match detections against the labels of a fixture
+pub fn score_fixture(name: &str, labels: &Labels, detections: &[Detection]) -> FixtureReport {
+    let mut scores: BTreeMap<String, Score> = labels.algorithms.iter()
+        .map(|algorithm| (algorithm.clone(), Score::default()))
+        .collect();
+    let mut missed = Vec::new();
+    let mut unexpected = Vec::new();
+
+    for expected in &labels.expected {
+        let score = scores.entry(expected.algorithm.clone()).or_default();
+        if detections.iter().any(|detection| expected.matches(detection, labels.tolerance)) {
+            score.true_positives += 1;
+        } else {
+            score.false_negatives += 1;
+            missed.push(expected.clone());
+        }
+    }
+
+    // Repeats of a labelled detection are fine, anything unlabelled is a false positive
+    for detection in detections {
+        if !labels.algorithms.contains(&detection.algorithm) {
+            continue;
+        }
+        if labels.expected.iter().any(|expected| expected.matches(detection, labels.tolerance)) {
+            continue;
+        }
+        scores.entry(detection.algorithm.clone()).or_default().false_positives += 1;
+        unexpected.push(detection.clone());
+    }
+
+    FixtureReport {
+        name: name.to_string(),
+        scores,
+        missed,
+        unexpected,
+    }
+}
+
+/// Run and score every fixture in the corpus
+pub fn run_corpus(dir: &Path) -> Result<Vec<FixtureReport>, Error> {
+    let mut reports = Vec::new();
+    for fixture in load_corpus(dir)? {
+        let detections = run_fixture(&fixture, corpus_algorithms())?;
+        reports.push(score_fixture(&fixture.name, &fixture.labels, &detections));
+    }
+    Ok(reports)
+}
+
+/// Print per-fixture results and the precision/recall of every algorithm
+pub fn print_report(reports: &[FixtureReport]) {
+    let mut totals: BTreeMap<String, Score> = BTreeMap::new();
+
+    for report in reports {
+        println!("{} {}", if report.passed() { "PASS" } else { "FAIL" }, report.name);
+        for expected in &report.missed {
+            println!("  missed {} {:?} for player {} at tick {}",
+                expected.algorithm, expected.kind.as_deref().unwrap_or("any"), expected.player, expected.tick);
+        }
+        for detection in &report.unexpected {
+            println!("  unexpected {} {} for player {} at tick {}",
+                detection.algorithm, detection.data.get("type").and_then(|t| t.as_str()).unwrap_or("?"),
+                detection.player, detection.tick);
+        }
+        for (algorithm, score) in &report.scores {
+            totals.entry(algorithm.clone()).or_default().add(score);
+        }
+    }
+
+    println!();
+    println!("{:<24} {:>4} {:>4} {:>4} {:>10} {:>8}", "algorithm", "tp", "fp", "fn", "precision", "recall");
+    for (algorithm, score) in &totals {
+        println!("{:<24} {:>4} {:>4} {:>4} {:>10.2} {:>8.2}",
+            algorithm, score.true_positives, score.false_positives, score.false_negatives,
+            score.precision(), score.recall());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn corpus_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(CORPUS_DIR)
+    }
+
+    fn run_named(name: &str) -> FixtureReport {
+        run_named_every(name, 1)
+    }
+
+    fn run_named_every(name: &str, stride: u32) -> FixtureReport {
+        let fixture = load_corpus(&corpus_dir())
+            .expect("corpus should load")
+            .into_iter()
+            .find(|fixture| fixture.name == name)
+            .unwrap_or_else(|| panic!("fixture {} missing", name));
+        let detections = run_fixture_every(&fixture, corpus_algorithms(), stride).expect("fixture should replay");
+        score_fixture(&format!("{} every {} ticks", fixture.name, stride), &fixture.labels, &detections)
+    }
+
+    fn assert_passes(report: &FixtureReport) {
+        assert!(report.missed.is_empty(), "{}: missed {:?}", report.name, report.missed);
+        assert!(
+            report.unexpected.is_empty(),
+            "{}: unexpected {:?}",
+            report.name,
+            report.unexpected.iter().map(|d| (&d.algorithm, d.player, d.tick)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn scoring_counts_each_label_once() {
+        let labels = Labels {
+            description: String::new(),
+            demo_kind: None,
+            recorder: None,
+            algorithms: vec!["a".to_string()],
+            tolerance: 2,
+            expected: vec![ExpectedDetection {
+                algorithm: "a".to_string(),
+                player: 1,
+                tick: 100,
+                tick_end: None,
+                kind: Some("flick".to_string()),
+            }],
+        };
+        let detection = |tick: u32, player: u64| Detection {
+            tick,
+            algorithm: "a".to_string(),
+            player,
+            data: json!({ "type": "flick" }),
+            timestamp: None,
+        };
+
+        let report = score_fixture("scoring", &labels, &[detection(101, 1), detection(99, 1), detection(100, 2)]);
+        let score = report.scores["a"];
+        assert_eq!(score.true_positives, 1);
+        assert_eq!(score.false_positives, 1);
+        assert_eq!(score.false_negatives, 0);
+        assert_eq!(score.precision(), 0.5);
+        assert_eq!(score.recall(), 1.0);
+    }
+
+    #[test]
+    fn corpus_loads() {
+        let fixtures = load_corpus(&corpus_dir()).expect("corpus should load");
+        assert!(!fixtures.is_empty());
+        for fixture in &fixtures {
+            assert!(!fixture.labels.algorithms.is_empty(), "{} grades nothing", fixture.name);
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_pitch() {
+        assert_passes(&run_named("oob_pitch"));
+    }
+
+    #[test]
+    fn out_of_bounds_pitch_in_demo() {
+        assert_passes(&run_named("oob_pitch_demo"));
+    }
+
+    #[test]
+    fn perfect_bunnyhop() {
+        assert_passes(&run_named("bhop_script"));
+    }
+
+    #[test]
+    fn sniper_flick_headshot() {
+        assert_passes(&run_named("sniper_flick"));
+    }
+
+    #[test]
+    fn movement_scripts() {
+        assert_passes(&run_named("movement_script"));
+    }
+
+    #[test]
+    fn backtracked_hits() {
+        assert_passes(&run_named("backtrack"));
+    }
+
+    #[test]
+    fn tracking_through_walls() {
+        assert_passes(&run_named("wallhack_tracking"));
+    }
+
+    #[test]
+    fn smoothed_aim() {
+        assert_passes(&run_named("smoothing"));
+    }
+
+    #[test]
+    fn lower_snapshot_rates() {
+        // SourceTV below 66 snapshots a second never records two consecutive ticks
+        for name in ["smoothing", "bhop_script", "movement_script", "sniper_flick", "clean_lobby"] {
+            for stride in [2, 3] {
+                assert_passes(&run_named_every(name, stride));
+            }
+        }
+    }
+
+    #[test]
+    fn clean_lobby_has_no_detections() {
+        let report = run_named("clean_lobby");
+        // Every algorithm has to stay quiet here, not just the ones a fixture targets
+        for algorithm in corpus_algorithms() {
+            assert!(report.scores.contains_key(algorithm.algorithm_name()), "clean_lobby doesn't grade {}", algorithm.algorithm_name());
+        }
+        assert_passes(&report);
+    }
+}
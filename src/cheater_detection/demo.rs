@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use bitbuffer::BitRead;
+use tf_demo_parser::demo::header::Header;
+use tf_demo_parser::demo::packet::Packet;
+use tf_demo_parser::demo::parser::gamestateanalyser::{GameState, GameStateAnalyser, PlayerState as GamePlayerState};
+use tf_demo_parser::demo::parser::{DemoHandler, RawPacketStream};
+use tf_demo_parser::{Demo, ParserState};
+
+use super::base::CheatAnalyser;
+use super::{bsp, PlayerState};
+
+/// Gets the player states of each tick before the analyser does
+pub type OnTick<'a> = dyn FnMut(u32, &HashMap<u64, PlayerState>, &CheatAnalyser, &ParserState) + 'a;
+
+/// Replay a demo packet by packet so the algorithms see the real game state every tick.
+/// `progress` gets the share of ticks replayed each time it grows by a percent and `log`
+/// status lines. The caller still has to `finish` the analyser
+pub fn replay_demo(
+    bytes: &[u8],
+    path: &Path,
+    map_dirs: &[PathBuf],
+    analyser: &mut CheatAnalyser,
+    on_tick: &mut OnTick,
+    progress: &dyn Fn(f32),
+    log: &dyn Fn(String),
+) -> Result<(), String> {
+    let demo = Demo::new(bytes);
+    let mut stream = demo.get_stream();
+    let header = Header::read(&mut stream)
+        .map_err(|e| format!("Failed to read demo header: {}", e))?;
+
+    // STV and POV demos record angles with different precision, the tick rate
+    // and file name turn detection ticks into demo time and playdemo commands
+    analyser.handle_header(&header);
+    analyser.set_demo_path(path);
+    log(format!("Demo recorded as {:?} by {}", analyser.get_demo_kind(), header.nick));
+
+    // Line of sight checks need the map, without it the algorithms fall back to heuristics
+    match bsp::find_map(&header.map, map_dirs) {
+        Ok(Some(map)) => {
+            log(format!("Loaded {} for line of sight checks", header.map));
+            analyser.set_map(Some(map));
+        }
+        Ok(None) => log(format!("Map {} not found, line of sight checks are skipped", header.map)),
+        Err(e) => log(format!("Failed to load map {}: {}", header.map, e)),
+    }
+
+    let mut handler = DemoHandler::with_analyser(GameStateAnalyser::new());
+    handler.handle_header(&header);
+    let mut packets = RawPacketStream::new(stream);
+
+    let mut last_tick = None;
+    let mut processed_ticks = 0;
+    let mut reported_percent = 0;
+
+    while let Some(packet) = packets.next(&handler.state_handler)
+        .map_err(|e| format!("Failed to parse demo: {}", e))?
+    {
+        let tick = u32::from(packet.tick());
+
+        // Damage, kills and player conditions only exist in the raw messages
+        if let Packet::Message(message) = &packet {
+            analyser.queue_hit_events(message, tick);
+            analyser.track_conditions(message, &handler.state_handler);
+        }
+
+        handler.handle_packet(packet)
+            .map_err(|e| format!("Failed to parse demo at tick {}: {}", tick, e))?;
+
+        // Several packets can share a tick, the algorithms only need it once
+        if last_tick == Some(tick) {
+            continue;
+        }
+        last_tick = Some(tick);
+        processed_ticks += 1;
+        if let Some(percent) = (tick * 100).checked_div(header.ticks) {
+            let percent = percent.min(100);
+            if percent > reported_percent {
+                reported_percent = percent;
+                progress(percent as f32 / 100.0);
+            }
+        }
+
+        let player_states = player_states(handler.borrow_output(), analyser);
+        on_tick(tick, &player_states, analyser, &handler.state_handler);
+
+        for player_state in player_states.into_values() {
+            analyser.update_player_state(player_state);
+        }
+        if let Err(e) = analyser.handle_tick_at(tick, &handler.state_handler) {
+            return Err(format!("Cheat analysis failed at tick {}: {}", tick, e));
+        }
+    }
+
+    log(format!("Processed {} ticks", processed_ticks));
+    Ok(())
+}
+
+/// Convert the parser's game state into the per-player state the cheat algorithms use
+fn player_states(game_state: &GameState, analyser: &CheatAnalyser) -> HashMap<u64, PlayerState> {
+    game_state.players.iter()
+        .filter_map(|player| {
+            // Bots and unconnected slots have no usable steamid
+            let info = player.info.as_ref()?;
+            let steamid = crate::steamid_32_to_64(&info.steam_id)?.parse::<u64>().ok()?;
+            let alive = player.state == GamePlayerState::Alive;
+
+            Some((steamid, PlayerState {
+                steamid,
+                user_id: Some(u16::from(info.user_id)),
+                entity: Some(u32::from(info.entity_id)),
+                viewangles: alive.then_some((player.pitch_angle, player.view_angle, 0.0)),
+                position: alive.then_some((player.position.x, player.position.y, player.position.z)),
+                name: info.name.clone(),
+                class: Some(player.class as u8),
+                team: Some(player.team as u8),
+                scoped: analyser.is_zoomed(u32::from(info.entity_id)),
+            }))
+        })
+        .collect()
+}
@@ -0,0 +1,67 @@
+// Samples further apart than this are a hole in the recording rather than a lower snapshot
+// rate, SourceTV at tv_snapshotrate 16 on a 66 tick server records every 4-5 ticks
+pub const MAX_SAMPLE_GAP: u32 = 5;
+
+/// Ticks between two samples of a player, None when they're too far apart to take rates from
+pub fn sample_gap(previous: u32, current: u32) -> Option<u32> {
+    let gap = current.checked_sub(previous)?;
+    (1..=MAX_SAMPLE_GAP).contains(&gap).then_some(gap)
+}
+
+/// Angle delta properly handling wraparound at 360 degrees, in -180..180
+pub fn calculate_angle_delta(current: f32, previous: f32) -> f32 {
+    let diff = (current - previous).rem_euclid(360.0);
+    if diff > 180.0 {
+        diff - 360.0
+    } else {
+        diff
+    }
+}
+
+pub fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+pub fn std_dev(values: &[f32], mean: f32) -> f32 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / (values.len() - 1) as f32;
+    variance.sqrt()
+}
+
+pub fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let index = ((sorted.len() - 1) as f32 * p).round() as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+/// Median and median absolute deviation of one metric across the lobby
+pub struct Baseline {
+    pub median: f32,
+    mad: f32,
+}
+
+impl Baseline {
+    pub fn from_values(values: Vec<f32>) -> Self {
+        let median = percentile(&values, 0.5);
+        let deviations: Vec<f32> = values.iter().map(|v| (v - median).abs()).collect();
+        Self {
+            median,
+            mad: percentile(&deviations, 0.5),
+        }
+    }
+
+    /// Robust z-score, scaled so it matches a standard z-score for normal data
+    pub fn z_score(&self, value: f32) -> f32 {
+        let scale = (self.mad * 1.4826).max(1e-6);
+        (value - self.median) / scale
+    }
+}
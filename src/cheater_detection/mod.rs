@@ -1,20 +1,80 @@
 pub mod viewangles;
+pub mod smoothness;
+pub mod movement;
+pub mod backtrack;
+pub mod sniper;
+pub mod wallhack;
+pub mod corpus;
+pub mod tracegen;
+pub mod bsp;
+pub mod demo;
+pub mod math;
 pub mod base;
 
 use std::sync::atomic::AtomicBool;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
-use tf_demo_parser::{demo::{message::Message, data::DemoTick}, MessageType, ParserState};
+use tf_demo_parser::{demo::{message::Message, data::DemoTick, header::Header}, MessageType, ParserState};
 use anyhow::Error;
 
 pub static SILENT: AtomicBool = AtomicBool::new(false);
 
+/// Algorithms run by the Demo Checker alongside the viewangle CSV extraction
+pub fn all_algorithms() -> Vec<Box<dyn CheatAlgorithm>> {
+    vec![
+        Box::new(smoothness::SmoothnessAnalyzer::new()),
+        Box::new(movement::MovementAnalyzer::new()),
+        Box::new(backtrack::BacktrackAnalyzer::new()),
+        Box::new(sniper::SniperAnalyzer::new()),
+        Box::new(wallhack::WallhackAnalyzer::new()),
+    ]
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Detection {
     pub tick: u32,
     pub algorithm: String,
     pub player: u64,
-    pub data: Value
+    pub data: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DemoTimestamp>, // filled in by `CheatAnalyser`
+}
+
+// Ticks to rewind before a detection so the lead-up is visible, about 3 seconds
+const GOTOTICK_LEAD: u32 = 200;
+
+/// Where a detection happened in the demo, for jumping to it in-game
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DemoTimestamp {
+    pub seconds: f32,
+    pub elapsed: String,  // mm:ss since the start of the demo
+    pub playdemo: String, // ready to paste into the TF2 console
+}
+
+impl DemoTimestamp {
+    pub fn new(tick: u32, tick_interval: f32, playdemo_path: &str) -> Self {
+        let seconds = tick as f32 * tick_interval;
+        let whole_seconds = seconds as u32;
+        Self {
+            seconds,
+            elapsed: format!("{:02}:{:02}", whole_seconds / 60, whole_seconds % 60),
+            playdemo: format!("playdemo {}; demo_gototick {}", playdemo_path, tick.saturating_sub(GOTOTICK_LEAD)),
+        }
+    }
+}
+
+/// Demo path as `playdemo` expects it, relative to the tf folder and without extension
+pub fn playdemo_path(path: &std::path::Path) -> String {
+    let stem = path.with_extension("");
+    let components: Vec<String> = stem.components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    match components.iter().rposition(|component| component.eq_ignore_ascii_case("tf")) {
+        Some(tf) if tf + 1 < components.len() => components[tf + 1..].join("/"),
+        // Outside the game folder playdemo can't reach it, the name is the best we can do
+        _ => components.last().cloned().unwrap_or_default(),
+    }
 }
 
 pub trait CheatAlgorithm {
@@ -35,6 +95,12 @@ pub trait CheatAlgorithm {
         Ok(())
     }
 
+    /// Whether the algorithm gives meaningful results on this kind of demo,
+    /// algorithms returning false are skipped for the whole demo
+    fn supports_demo_kind(&self, _kind: DemoKind) -> bool {
+        true
+    }
+
     fn on_tick(&mut self, state: &CheatAnalyserState, parser_state: &ParserState) -> Result<Vec<Detection>, Error>;
 
     fn handled_messages(&self) -> Result<Vec<MessageType>, bool>;
@@ -46,16 +112,112 @@ pub trait CheatAlgorithm {
     }
 }
 
+/// Who recorded the demo, which decides how precise the recorded angles are
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DemoKind {
+    Pov,      // recorded by a player, their own angles come from usercmds
+    SourceTv, // recorded by the server, every player's angles are networked
+    Unknown,
+}
+
+// Default tv_name, SourceTV demos are recorded under it
+const SOURCETV_NAME: &str = "SourceTV";
+// Networked eye angles are quantised, yaw in 1024 steps over 360 degrees and pitch
+// in 256 steps over 180 degrees
+const NETWORKED_YAW_PRECISION: f32 = 360.0 / 1024.0;
+const NETWORKED_PITCH_PRECISION: f32 = 180.0 / 256.0;
+// Used when the header doesn't give a usable duration, TF2 runs at 66.67 ticks per second
+pub const DEFAULT_TICK_INTERVAL: f32 = 0.015;
+
+impl DemoKind {
+    /// Demo kind from the header. A server with a renamed tv_name looks like a POV demo
+    /// here, `CheatAnalyser` corrects that when no player with the recorder's name shows up
+    pub fn from_header(header: &Header) -> Self {
+        if header.nick.is_empty() {
+            DemoKind::Unknown
+        } else if header.nick == SOURCETV_NAME {
+            DemoKind::SourceTv
+        } else {
+            DemoKind::Pov
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CheatAnalyserState {
     pub tick: u32,
+    pub demo_kind: DemoKind,
+    pub tick_interval: f32, // seconds per tick, from the demo header
     pub player_states: std::collections::HashMap<u64, PlayerState>,
+    pub hit_events: Vec<HitEvent>, // damage and kills that happened this tick
+    pub map: Option<std::sync::Arc<bsp::BspMap>>, // map geometry, when the .bsp could be found
+    // Players whose angles come from usercmds, the recorder of a POV demo. Everyone
+    // else's angles are networked and quantised
+    pub usercmd_players: std::collections::HashSet<u64>,
+}
+
+impl CheatAnalyserState {
+    /// Smallest yaw difference that can be told apart for a player, in degrees
+    pub fn yaw_precision(&self, steamid: u64) -> f32 {
+        if self.usercmd_players.contains(&steamid) {
+            0.0
+        } else {
+            NETWORKED_YAW_PRECISION
+        }
+    }
+
+    /// Same as `yaw_precision` for pitch, which is networked coarser
+    pub fn pitch_precision(&self, steamid: u64) -> f32 {
+        if self.usercmd_players.contains(&steamid) {
+            0.0
+        } else {
+            NETWORKED_PITCH_PRECISION
+        }
+    }
+
+    /// Line of sight between two points, None when the map isn't loaded
+    pub fn is_visible(&self, from: (f32, f32, f32), to: (f32, f32, f32)) -> Option<bool> {
+        self.map.as_ref().map(|map| map.is_visible(from, to))
+    }
 }
 
 #[derive(Clone)]
 pub struct PlayerState {
     pub steamid: u64,
+    pub user_id: Option<u16>, // in-game user id, used to resolve game events
+    pub entity: Option<u32>,  // entity index, what spec_player takes when watching the demo
     pub viewangles: Option<(f32, f32, f32)>, // pitch, yaw, roll
     pub position: Option<(f32, f32, f32)>,    // x, y, z
     pub name: String,
-} 
\ No newline at end of file
+    pub class: Option<u8>,    // TF2 class id, see `class_name`
+    pub team: Option<u8>,     // 2 = RED, 3 = BLU
+    pub scoped: Option<bool>, // sniper zoom state
+}
+
+/// Name of a TF2 class id as used by the game (1 = scout ... 9 = engineer)
+pub fn class_name(class: u8) -> &'static str {
+    match class {
+        1 => "scout",
+        2 => "sniper",
+        3 => "soldier",
+        4 => "demoman",
+        5 => "medic",
+        6 => "heavy",
+        7 => "pyro",
+        8 => "spy",
+        9 => "engineer",
+        _ => "unknown",
+    }
+}
+
+/// A player damaging or killing another player, from `player_hurt`/`player_death` events
+#[derive(Clone, Debug)]
+pub struct HitEvent {
+    pub tick: u32,
+    pub attacker: u64,
+    pub victim: u64,
+    pub weapon: Option<String>, // only known for kills
+    pub damage: u16,
+    pub headshot: bool,
+    pub kill: bool,
+}
\ No newline at end of file
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use anyhow::Error;
+use serde_json::json;
+use tf_demo_parser::{
+    demo::{data::DemoTick, message::Message},
+    MessageType, ParserState,
+};
+
+use super::{CheatAlgorithm, CheatAnalyserState, Detection};
+use super::math::{calculate_angle_delta, sample_gap};
+
+// Vertical speed (units/s) treated as standing on the ground
+const GROUND_VELOCITY_EPSILON: f32 = 1.0;
+// Vertical speed (units/s) at the start of a jump, TF2's jump impulse is ~289
+const JUMP_VELOCITY_MIN: f32 = 200.0;
+// Ticks on the ground that still count as jumping on the landing tick
+const PERFECT_HOP_GROUND_TICKS: u32 = 1;
+// Consecutive perfect hops needed before a chain is reported
+const MIN_PERFECT_HOPS: u32 = 7;
+// Airborne ticks needed before a strafe segment is judged
+const MIN_STRAFE_TICKS: u32 = 20;
+// Share of airborne ticks gaining speed that marks a segment as auto-strafed
+const AUTO_STRAFE_GAIN_RATIO: f32 = 0.9;
+// Auto-strafed segments needed before a player is reported
+const MIN_AUTO_STRAFE_SEGMENTS: usize = 3;
+// Ground speed gain per tick (units/s) far beyond sv_accelerate 10 at 400 wishspeed
+const MAX_GROUND_ACCELERATION: f32 = 180.0;
+// Impossible acceleration ticks needed within one window before reporting
+const MIN_ACCELERATION_TICKS: u32 = 5;
+const ACCELERATION_WINDOW: u32 = 66;
+// Larger moves per tick are respawns or teleporters, not movement
+const TELEPORT_DISTANCE: f32 = 300.0;
+
+/// Movement of a player on the previous tick
+struct MovementSample {
+    tick: u32,
+    position: (f32, f32, f32),
+    yaw: Option<f32>,
+    horizontal_speed: Option<f32>,
+    vertical_velocity: Option<f32>,
+}
+
+/// An airborne stretch used for auto-strafe detection
+struct AirSegment {
+    start_tick: u32,
+    ticks: u32,
+    gaining_ticks: u32,
+    turning_ticks: u32,
+}
+
+#[derive(Default)]
+struct MovementTracker {
+    airborne: bool,
+    ground_ticks: u32,
+    // Start tick and length of the current chain of perfect hops
+    hop_chain: Option<(u32, u32)>,
+    last_hop_tick: u32,
+    air_segment: Option<AirSegment>,
+    bhop_chains: Vec<(u32, u32, u32)>,       // start tick, end tick, hops
+    auto_strafe_segments: Vec<(u32, u32, f32)>, // start tick, end tick, gain ratio
+    acceleration_ticks: Vec<(u32, f32, u32)>,  // tick, speed gain, ticks since the previous sample
+}
+
+/// Detects scripted movement: perfect consecutive bunnyhops, auto-strafe
+/// speed gain synchronised with yaw changes, and ground acceleration that
+/// the movement code can't produce.
+pub struct MovementAnalyzer {
+    previous: HashMap<u64, MovementSample>,
+    trackers: HashMap<u64, MovementTracker>,
+    player_names: HashMap<u64, String>,
+}
+
+impl MovementAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            previous: HashMap::new(),
+            trackers: HashMap::new(),
+            player_names: HashMap::new(),
+        }
+    }
+
+    fn check_movement(&mut self, player_id: u64, position: (f32, f32, f32), yaw: Option<f32>, tick: u32, tick_interval: f32) {
+        let tracker = self.trackers.entry(player_id).or_default();
+
+        // Velocities are taken per tick, SourceTV only records a sample every few ticks
+        let gap = match self.previous.get(&player_id).and_then(|p| sample_gap(p.tick, tick)) {
+            Some(gap) => gap,
+            None => {
+                self.previous.insert(player_id, MovementSample {
+                    tick,
+                    position,
+                    yaw,
+                    horizontal_speed: None,
+                    vertical_velocity: None,
+                });
+                return;
+            }
+        };
+        let previous = &self.previous[&player_id];
+
+        let (dx, dy, dz) = (
+            position.0 - previous.position.0,
+            position.1 - previous.position.1,
+            position.2 - previous.position.2,
+        );
+
+        // Respawns and teleporters would look like infinite acceleration
+        if (dx * dx + dy * dy + dz * dz).sqrt() > TELEPORT_DISTANCE * gap as f32 {
+            Self::end_air_segment(tracker, tick);
+            tracker.hop_chain = None;
+            self.previous.insert(player_id, MovementSample {
+                tick,
+                position,
+                yaw,
+                horizontal_speed: None,
+                vertical_velocity: None,
+            });
+            return;
+        }
+
+        let elapsed = gap as f32 * tick_interval;
+        let horizontal_speed = (dx * dx + dy * dy).sqrt() / elapsed;
+        let vertical_velocity = dz / elapsed;
+        // Per tick, like the limits below
+        let speed_gain = previous.horizontal_speed.map(|prev| (horizontal_speed - prev) / gap as f32);
+        let yaw_delta = match (yaw, previous.yaw) {
+            (Some(curr), Some(prev)) => Some(calculate_angle_delta(curr, prev) / gap as f32),
+            _ => None,
+        };
+        // A sample can't tell one tick on the ground from a few
+        let hop_ground_ticks = PERFECT_HOP_GROUND_TICKS.max(gap);
+
+        let jumped = previous.vertical_velocity.map_or(false, |prev| {
+            prev <= GROUND_VELOCITY_EPSILON && if gap == 1 {
+                vertical_velocity > JUMP_VELOCITY_MIN
+            } else {
+                // The jump can be on any tick between two samples, and the landing before
+                // it too, so the averaged velocity only turns upward by part of the impulse
+                vertical_velocity > GROUND_VELOCITY_EPSILON && vertical_velocity - prev > JUMP_VELOCITY_MIN / gap as f32
+            }
+        });
+        let on_ground = !jumped && vertical_velocity.abs() <= GROUND_VELOCITY_EPSILON
+            && previous.vertical_velocity.map_or(false, |v| v.abs() <= GROUND_VELOCITY_EPSILON || v < 0.0);
+
+        if jumped {
+            // Jumping while still airborne from the previous hop, or on the
+            // landing tick itself, is a perfect hop
+            let perfect = tracker.airborne || tracker.ground_ticks <= hop_ground_ticks;
+            if perfect && tracker.last_hop_tick > 0 {
+                match tracker.hop_chain.as_mut() {
+                    Some(chain) => chain.1 += 1,
+                    None => tracker.hop_chain = Some((tracker.last_hop_tick, 2)),
+                }
+            } else {
+                Self::end_hop_chain(tracker);
+            }
+            Self::end_air_segment(tracker, tick);
+            tracker.last_hop_tick = tick;
+            tracker.airborne = true;
+            tracker.ground_ticks = 0;
+            tracker.air_segment = Some(AirSegment {
+                start_tick: tick,
+                ticks: 0,
+                gaining_ticks: 0,
+                turning_ticks: 0,
+            });
+        } else if on_ground {
+            if tracker.airborne {
+                Self::end_air_segment(tracker, tick);
+            }
+            tracker.airborne = false;
+            tracker.ground_ticks += gap;
+            if tracker.ground_ticks > hop_ground_ticks {
+                Self::end_hop_chain(tracker);
+            }
+
+            // sv_accelerate caps how fast speed can build up on the ground
+            if let Some(gain) = speed_gain {
+                if gain > MAX_GROUND_ACCELERATION {
+                    tracker.acceleration_ticks.push((tick, gain, gap));
+                }
+            }
+        } else if let Some(segment) = tracker.air_segment.as_mut() {
+            segment.ticks += gap;
+            if speed_gain.map_or(false, |gain| gain > 0.0) {
+                segment.gaining_ticks += gap;
+                if yaw_delta.map_or(false, |d| d.abs() > 0.5) {
+                    segment.turning_ticks += gap;
+                }
+            }
+        }
+
+        self.previous.insert(player_id, MovementSample {
+            tick,
+            position,
+            yaw,
+            horizontal_speed: Some(horizontal_speed),
+            vertical_velocity: Some(vertical_velocity),
+        });
+    }
+
+    fn end_hop_chain(tracker: &mut MovementTracker) {
+        if let Some((start, hops)) = tracker.hop_chain.take() {
+            if hops >= MIN_PERFECT_HOPS {
+                tracker.bhop_chains.push((start, tracker.last_hop_tick, hops));
+            }
+        }
+    }
+
+    fn end_air_segment(tracker: &mut MovementTracker, tick: u32) {
+        if let Some(segment) = tracker.air_segment.take() {
+            if segment.ticks < MIN_STRAFE_TICKS {
+                return;
+            }
+            // Humans stop gaining speed between strafes, scripts gain on every
+            // tick and only while the view is turning
+            let gain_ratio = segment.gaining_ticks as f32 / segment.ticks as f32;
+            let turning_ratio = if segment.gaining_ticks > 0 {
+                segment.turning_ticks as f32 / segment.gaining_ticks as f32
+            } else {
+                0.0
+            };
+            if gain_ratio >= AUTO_STRAFE_GAIN_RATIO && turning_ratio >= AUTO_STRAFE_GAIN_RATIO {
+                tracker.auto_strafe_segments.push((segment.start_tick, tick, gain_ratio));
+            }
+        }
+    }
+
+    /// Group impossible acceleration ticks into windows dense enough to rule out knockback
+    fn acceleration_ranges(ticks: &[(u32, f32, u32)]) -> Vec<(u32, u32, u32, f32)> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while start < ticks.len() {
+            let mut end = start;
+            while end + 1 < ticks.len() && ticks[end + 1].0 - ticks[start].0 <= ACCELERATION_WINDOW {
+                end += 1;
+            }
+            let count: u32 = ticks[start..=end].iter().map(|(_, _, gap)| *gap).sum();
+            if count >= MIN_ACCELERATION_TICKS {
+                let max_gain = ticks[start..=end].iter().map(|(_, gain, _)| *gain).fold(0.0f32, f32::max);
+                ranges.push((ticks[start].0, ticks[end].0, count, max_gain));
+                start = end + 1;
+            } else {
+                start += 1;
+            }
+        }
+        ranges
+    }
+}
+
+impl CheatAlgorithm for MovementAnalyzer {
+    fn algorithm_name(&self) -> &str {
+        "movement_analyzer"
+    }
+
+    fn handled_messages(&self) -> Result<Vec<MessageType>, bool> {
+        Ok(vec![])
+    }
+
+    fn on_tick(&mut self, state: &CheatAnalyserState, _parser_state: &ParserState) -> Result<Vec<Detection>, Error> {
+        for (player_id, player_state) in &state.player_states {
+            if !player_state.name.is_empty() {
+                self.player_names.insert(*player_id, player_state.name.clone());
+            }
+
+            if let Some(position) = player_state.position {
+                let yaw = player_state.viewangles.map(|(_, yaw, _)| yaw);
+                self.check_movement(*player_id, position, yaw, state.tick, state.tick_interval);
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    fn on_message(&mut self, _message: &Message, _state: &CheatAnalyserState, _parser_state: &ParserState, _tick: DemoTick) -> Result<Vec<Detection>, Error> {
+        Ok(vec![])
+    }
+
+    fn finish(&mut self) -> Result<Vec<Detection>, Error> {
+        let mut detections = Vec::new();
+
+        for (player_id, tracker) in &mut self.trackers {
+            Self::end_hop_chain(tracker);
+
+            let player_name = self.player_names.get(player_id).cloned().unwrap_or_default();
+
+            if let Some((first_tick, _, _)) = tracker.bhop_chains.first() {
+                detections.push(Detection {
+                    tick: *first_tick,
+                    algorithm: "movement_analyzer".to_string(),
+                    player: *player_id,
+                    data: json!({
+                        "type": "perfect_bunnyhop",
+                        "player_name": player_name,
+                        "chains": tracker.bhop_chains.len(),
+                        "longest_chain": tracker.bhop_chains.iter().map(|(_, _, hops)| *hops).max(),
+                        "evidence": tracker.bhop_chains.iter().map(|(start, end, hops)| json!({
+                            "start_tick": start,
+                            "end_tick": end,
+                            "hops": hops,
+                        })).collect::<Vec<_>>(),
+                    }),
+                    timestamp: None,
+                });
+            }
+
+            if tracker.auto_strafe_segments.len() >= MIN_AUTO_STRAFE_SEGMENTS {
+                detections.push(Detection {
+                    tick: tracker.auto_strafe_segments[0].0,
+                    algorithm: "movement_analyzer".to_string(),
+                    player: *player_id,
+                    data: json!({
+                        "type": "auto_strafe",
+                        "player_name": player_name,
+                        "segments": tracker.auto_strafe_segments.len(),
+                        "evidence": tracker.auto_strafe_segments.iter().map(|(start, end, ratio)| json!({
+                            "start_tick": start,
+                            "end_tick": end,
+                            "gain_ratio": ratio,
+                        })).collect::<Vec<_>>(),
+                    }),
+                    timestamp: None,
+                });
+            }
+
+            let ranges = Self::acceleration_ranges(&tracker.acceleration_ticks);
+            if let Some((first_tick, _, _, _)) = ranges.first() {
+                detections.push(Detection {
+                    tick: *first_tick,
+                    algorithm: "movement_analyzer".to_string(),
+                    player: *player_id,
+                    data: json!({
+                        "type": "impossible_acceleration",
+                        "player_name": player_name,
+                        "limit": MAX_GROUND_ACCELERATION,
+                        "evidence": ranges.iter().map(|(start, end, count, max_gain)| json!({
+                            "start_tick": start,
+                            "end_tick": end,
+                            "ticks": count,
+                            "max_speed_gain": max_gain,
+                        })).collect::<Vec<_>>(),
+                    }),
+                    timestamp: None,
+                });
+            }
+        }
+
+        self.previous.clear();
+        Ok(detections)
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use anyhow::Error;
+use serde_json::json;
+use tf_demo_parser::{
+    demo::{data::DemoTick, message::Message},
+    MessageType, ParserState,
+};
+
+use super::{CheatAlgorithm, CheatAnalyserState, DemoKind, Detection};
+use super::math::{calculate_angle_delta, mean, percentile, sample_gap, std_dev, Baseline};
+
+// Below this angular speed (degrees/tick) the player is considered to be holding still
+const MIN_MOVING_VELOCITY: f32 = 0.5;
+// Players need at least this many moving ticks before their stats are trusted
+const MIN_MOVING_TICKS: u32 = 200;
+// A lobby baseline needs at least this many players with usable stats
+const MIN_LOBBY_PLAYERS: usize = 3;
+// Robust z-score at which a metric is considered anomalous
+const Z_SCORE_THRESHOLD: f32 = 3.0;
+// Relative tolerance for two consecutive deltas to count as the same step
+const LINEAR_TOLERANCE: f32 = 0.02;
+// Distance from the mouse step grid (as a fraction of a step) that counts as off-grid
+const OFF_GRID_TOLERANCE: f32 = 0.1;
+
+/// Angles and derivatives from the previous tick of a player
+struct AimSample {
+    tick: u32,
+    pitch: f32,
+    yaw: f32,
+    delta: Option<(f32, f32)>,  // pitch, yaw delta per tick since the previous sample
+    velocity: Option<f32>,
+    acceleration: Option<f32>,
+}
+
+/// Raw per-player distributions collected over the whole demo
+#[derive(Default)]
+struct AimStats {
+    velocities: Vec<f32>,
+    accelerations: Vec<f32>,
+    jerks: Vec<f32>,
+    yaw_deltas: Vec<f32>,
+    moving_samples: usize,
+    moving_ticks: u32, // the samples can be several ticks apart
+    linear_samples: usize,
+    current_linear_run: Option<(u32, u32)>,  // start tick, length
+    longest_linear_run: Option<(u32, u32)>,
+    first_tick: Option<u32>,
+    usercmd: bool, // angles at full precision, only then is the mouse step grid intact
+}
+
+/// Summary metrics compared against the lobby baseline
+#[derive(Clone, Copy)]
+struct AimMetrics {
+    velocity_cv: f32,     // stddev / mean of angular velocity while moving
+    jerk_ratio: f32,      // mean |jerk| / mean velocity
+    linear_ratio: f32,    // share of moving ticks that repeat the previous step exactly
+    off_grid_ratio: f32,  // share of yaw deltas that don't sit on the mouse step grid
+}
+
+/// Statistical aim analysis that compares every player's angular velocity,
+/// acceleration and jerk distributions against the rest of the lobby, to
+/// surface smoothed aimbots that never trip a single-tick magnitude check.
+pub struct SmoothnessAnalyzer {
+    previous: HashMap<u64, AimSample>,
+    stats: HashMap<u64, AimStats>,
+    player_names: HashMap<u64, String>,
+}
+
+impl SmoothnessAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            previous: HashMap::new(),
+            stats: HashMap::new(),
+            player_names: HashMap::new(),
+        }
+    }
+
+    fn record_angles(&mut self, player_id: u64, pitch: f32, yaw: f32, tick: u32, precision: f32) {
+        let stats = self.stats.entry(player_id).or_default();
+        stats.usercmd = precision == 0.0;
+        stats.first_tick.get_or_insert(tick);
+
+        // Derivatives are taken per tick, SourceTV only records a sample every few ticks
+        let previous = self.previous.get(&player_id)
+            .and_then(|p| sample_gap(p.tick, tick).map(|gap| (p, gap as f32)));
+
+        let raw_yaw_delta = previous.map(|(p, _)| calculate_angle_delta(yaw, p.yaw));
+        let delta = previous.zip(raw_yaw_delta).map(|((p, gap), dy)| ((pitch - p.pitch) / gap, dy / gap));
+        let velocity = delta.map(|(dp, dy)| (dp * dp + dy * dy).sqrt());
+        let acceleration = match (velocity, previous.and_then(|(p, gap)| p.velocity.map(|prev_v| (prev_v, gap)))) {
+            (Some(v), Some((prev_v, gap))) => Some((v - prev_v) / gap),
+            _ => None,
+        };
+        let jerk = match (acceleration, previous.and_then(|(p, gap)| p.acceleration.map(|prev_a| (prev_a, gap)))) {
+            (Some(a), Some((prev_a, gap))) => Some((a - prev_a) / gap),
+            _ => None,
+        };
+
+        if let (Some((dp, dy)), Some(v)) = (delta, velocity) {
+            if v > MIN_MOVING_VELOCITY {
+                stats.moving_samples += 1;
+                stats.moving_ticks += previous.map_or(1, |(_, gap)| gap as u32);
+                stats.velocities.push(v);
+                // Whole mouse counts add up to whole counts, the grid holds for the raw delta
+                if let Some(raw_dy) = raw_yaw_delta.filter(|raw_dy| *raw_dy != 0.0) {
+                    stats.yaw_deltas.push(raw_dy);
+                }
+                if let Some(a) = acceleration {
+                    stats.accelerations.push(a);
+                }
+                if let Some(j) = jerk {
+                    stats.jerks.push(j);
+                }
+
+                // An aimbot interpolating linearly towards a target produces
+                // the exact same step tick after tick
+                let is_linear = previous
+                    .and_then(|(p, gap)| p.delta.map(|prev_delta| (prev_delta, gap)))
+                    .map(|((prev_dp, prev_dy), gap)| {
+                        let diff = ((dp - prev_dp).powi(2) + (dy - prev_dy).powi(2)).sqrt();
+                        diff < LINEAR_TOLERANCE * v + 0.001 + precision / gap
+                    })
+                    .unwrap_or(false);
+
+                if is_linear {
+                    stats.linear_samples += 1;
+                    let start = previous.map_or(tick, |(p, _)| p.tick);
+                    let run = stats.current_linear_run.get_or_insert((start, 1));
+                    run.1 += 1;
+                    let run = *run;
+                    if stats.longest_linear_run.map_or(true, |(_, len)| run.1 > len) {
+                        stats.longest_linear_run = Some(run);
+                    }
+                } else {
+                    stats.current_linear_run = None;
+                }
+            } else {
+                stats.current_linear_run = None;
+            }
+        }
+
+        self.previous.insert(player_id, AimSample {
+            tick,
+            pitch,
+            yaw,
+            delta,
+            velocity,
+            acceleration,
+        });
+    }
+
+    fn compute_metrics(stats: &AimStats) -> Option<AimMetrics> {
+        if stats.moving_ticks < MIN_MOVING_TICKS {
+            return None;
+        }
+
+        let mean_velocity = mean(&stats.velocities);
+        if mean_velocity <= 0.0 {
+            return None;
+        }
+        let velocity_cv = std_dev(&stats.velocities, mean_velocity) / mean_velocity;
+
+        let abs_jerks: Vec<f32> = stats.jerks.iter().map(|j| j.abs()).collect();
+        let jerk_ratio = mean(&abs_jerks) / mean_velocity;
+
+        let linear_ratio = stats.linear_samples as f32 / stats.moving_samples as f32;
+
+        Some(AimMetrics {
+            velocity_cv,
+            jerk_ratio,
+            linear_ratio,
+            off_grid_ratio: off_grid_ratio(&stats.yaw_deltas),
+        })
+    }
+}
+
+impl CheatAlgorithm for SmoothnessAnalyzer {
+    fn algorithm_name(&self) -> &str {
+        "smoothness_analyzer"
+    }
+
+    fn handled_messages(&self) -> Result<Vec<MessageType>, bool> {
+        Ok(vec![])
+    }
+
+    fn supports_demo_kind(&self, kind: DemoKind) -> bool {
+        // Quantisation noise on networked angles swamps the jerk and step metrics
+        kind != DemoKind::SourceTv
+    }
+
+    fn on_tick(&mut self, state: &CheatAnalyserState, _parser_state: &ParserState) -> Result<Vec<Detection>, Error> {
+        for (player_id, player_state) in &state.player_states {
+            if !player_state.name.is_empty() {
+                self.player_names.insert(*player_id, player_state.name.clone());
+            }
+
+            if let Some((pitch, yaw, _)) = player_state.viewangles {
+                let precision = state.yaw_precision(*player_id).max(state.pitch_precision(*player_id));
+                self.record_angles(*player_id, pitch, yaw, state.tick, precision);
+            }
+        }
+
+        // Everything is judged against the lobby at the end of the demo
+        Ok(vec![])
+    }
+
+    fn on_message(&mut self, _message: &Message, _state: &CheatAnalyserState, _parser_state: &ParserState, _tick: DemoTick) -> Result<Vec<Detection>, Error> {
+        Ok(vec![])
+    }
+
+    fn finish(&mut self) -> Result<Vec<Detection>, Error> {
+        let metrics: Vec<(u64, AimMetrics)> = self.stats.iter()
+            .filter_map(|(player_id, stats)| Self::compute_metrics(stats).map(|m| (*player_id, m)))
+            .collect();
+
+        if metrics.len() < MIN_LOBBY_PLAYERS {
+            self.previous.clear();
+            return Ok(vec![]);
+        }
+
+        let velocity_cv = Baseline::from_values(metrics.iter().map(|(_, m)| m.velocity_cv).collect());
+        let jerk_ratio = Baseline::from_values(metrics.iter().map(|(_, m)| m.jerk_ratio).collect());
+        let linear_ratio = Baseline::from_values(metrics.iter().map(|(_, m)| m.linear_ratio).collect());
+        // Off-grid deltas are only compared between players whose angles come from usercmds
+        let usercmd_off_grid: Vec<f32> = metrics.iter()
+            .filter(|(player_id, _)| self.stats[player_id].usercmd)
+            .map(|(_, m)| m.off_grid_ratio)
+            .collect();
+        let off_grid_usable = usercmd_off_grid.len() >= MIN_LOBBY_PLAYERS;
+        let off_grid_ratio = Baseline::from_values(usercmd_off_grid);
+
+        let mut detections = Vec::new();
+
+        for (player_id, m) in &metrics {
+            let z_velocity_cv = velocity_cv.z_score(m.velocity_cv);
+            let z_jerk_ratio = jerk_ratio.z_score(m.jerk_ratio);
+            let z_linear_ratio = linear_ratio.z_score(m.linear_ratio);
+            let z_off_grid_ratio = off_grid_ratio.z_score(m.off_grid_ratio);
+
+            // Smoothing shows up as unnaturally steady motion (low variation and jerk),
+            // repeated identical steps and sub-count mouse movement
+            let mut anomalies = Vec::new();
+            if z_velocity_cv < -Z_SCORE_THRESHOLD {
+                anomalies.push("steady_velocity");
+            }
+            if z_jerk_ratio < -Z_SCORE_THRESHOLD {
+                anomalies.push("low_jerk");
+            }
+            if z_linear_ratio > Z_SCORE_THRESHOLD {
+                anomalies.push("linear_interpolation");
+            }
+            // The mouse step grid is only intact in angles taken from usercmds
+            if z_off_grid_ratio > Z_SCORE_THRESHOLD && off_grid_usable && self.stats[player_id].usercmd {
+                anomalies.push("off_grid_deltas");
+            }
+
+            if anomalies.is_empty() {
+                continue;
+            }
+
+            let stats = &self.stats[player_id];
+            let tick = stats.longest_linear_run
+                .map(|(start, _)| start)
+                .or(stats.first_tick)
+                .unwrap_or(0);
+
+            detections.push(Detection {
+                tick,
+                algorithm: self.algorithm_name().to_string(),
+                player: *player_id,
+                data: json!({
+                    "type": "unnatural_smoothness",
+                    "player_name": self.player_names.get(player_id).cloned().unwrap_or_default(),
+                    "anomalies": anomalies,
+                    "usercmd_angles": stats.usercmd,
+                    "moving_samples": stats.moving_samples,
+                    "longest_linear_run": stats.longest_linear_run.map(|(start, len)| json!({
+                        "start_tick": start,
+                        "length": len,
+                    })),
+                    "metrics": {
+                        "velocity_cv": { "value": m.velocity_cv, "lobby_median": velocity_cv.median, "z": z_velocity_cv },
+                        "jerk_ratio": { "value": m.jerk_ratio, "lobby_median": jerk_ratio.median, "z": z_jerk_ratio },
+                        "linear_ratio": { "value": m.linear_ratio, "lobby_median": linear_ratio.median, "z": z_linear_ratio },
+                        "off_grid_ratio": { "value": m.off_grid_ratio, "lobby_median": off_grid_ratio.median, "z": z_off_grid_ratio },
+                    },
+                    "acceleration_p95": percentile(&stats.accelerations.iter().map(|a| a.abs()).collect::<Vec<_>>(), 0.95),
+                }),
+                timestamp: None,
+            });
+        }
+
+        self.previous.clear();
+        Ok(detections)
+    }
+}
+
+/// Share of yaw deltas that are not a whole multiple of the player's mouse step.
+/// Mouse input moves the view in multiples of sensitivity * m_yaw, so a human's
+/// small deltas cluster on a grid while smoothed aim produces fractional steps.
+fn off_grid_ratio(yaw_deltas: &[f32]) -> f32 {
+    let magnitudes: Vec<f32> = yaw_deltas.iter().map(|d| d.abs()).filter(|d| *d > 1e-4).collect();
+    if magnitudes.is_empty() {
+        return 0.0;
+    }
+
+    // The smallest common movements approximate one mouse count
+    let step = percentile(&magnitudes, 0.05);
+    if step <= 1e-4 {
+        return 0.0;
+    }
+
+    let off_grid = magnitudes.iter()
+        .filter(|d| {
+            let counts = *d / step;
+            (counts - counts.round()).abs() > OFF_GRID_TOLERANCE
+        })
+        .count();
+
+    off_grid as f32 / magnitudes.len() as f32
+}
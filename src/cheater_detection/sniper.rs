@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use anyhow::Error;
+use serde_json::json;
+use tf_demo_parser::{
+    demo::{data::DemoTick, message::Message},
+    MessageType, ParserState,
+};
+
+use super::{class_name, CheatAlgorithm, CheatAnalyserState, Detection, HitEvent};
+use super::math::{calculate_angle_delta, sample_gap};
+
+// Samples before the shot that are searched for a flick, ticks when every tick is recorded
+const FLICK_WINDOW: usize = 3;
+// Angular speed (degrees/tick) that counts as a flick
+const FLICK_THRESHOLD: f32 = 15.0;
+// Angular speed (degrees/tick) on the shot tick, a flick that lands dead still is suspicious
+const SETTLED_THRESHOLD: f32 = 1.0;
+// Unscoped -> scoped -> shot faster than this many ticks
+const INSTANT_SCOPE_WINDOW: u32 = 3;
+// Kills needed in one class before its headshot rate is judged
+const MIN_CLASS_KILLS: usize = 10;
+// Headshot rates no legitimate player sustains, per class
+const MAX_SNIPER_HEADSHOT_RATE: f32 = 0.85;
+const MAX_SPY_HEADSHOT_RATE: f32 = 0.7;
+// Ticks of angle and scope history kept per player
+const HISTORY_TICKS: usize = 8;
+
+const SNIPER_CLASS: u8 = 2;
+const SPY_CLASS: u8 = 8;
+
+/// Angles and zoom state of a player on one tick
+struct AimFrame {
+    tick: u32,
+    pitch: f32,
+    yaw: f32,
+    scoped: Option<bool>,
+}
+
+#[derive(Default)]
+struct ClassKills {
+    kills: usize,
+    headshots: usize,
+    first_headshot_tick: Option<u32>,
+}
+
+/// Looks at Sniper (and Ambassador) headshots for timing no human produces:
+/// a flick that lands dead on the head, unscoped-flick-scoped-shot inside a
+/// couple of ticks, and per-class headshot rates no legitimate player sustains.
+pub struct SniperAnalyzer {
+    history: HashMap<u64, VecDeque<AimFrame>>,
+    class_kills: HashMap<(u64, u8), ClassKills>,
+    player_names: HashMap<u64, String>,
+}
+
+impl SniperAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            history: HashMap::new(),
+            class_kills: HashMap::new(),
+            player_names: HashMap::new(),
+        }
+    }
+
+    /// Angular speed per tick between two frames, or None if they're too far apart
+    fn angular_speed(previous: &AimFrame, current: &AimFrame) -> Option<f32> {
+        let gap = sample_gap(previous.tick, current.tick)? as f32;
+        let pitch_delta = current.pitch - previous.pitch;
+        let yaw_delta = calculate_angle_delta(current.yaw, previous.yaw);
+        Some((pitch_delta * pitch_delta + yaw_delta * yaw_delta).sqrt() / gap)
+    }
+
+    fn record_frames(&mut self, state: &CheatAnalyserState) {
+        for (player_id, player_state) in &state.player_states {
+            if !player_state.name.is_empty() {
+                self.player_names.insert(*player_id, player_state.name.clone());
+            }
+
+            if let Some((pitch, yaw, _)) = player_state.viewangles {
+                let history = self.history.entry(*player_id).or_default();
+                if history.back().map_or(true, |frame| frame.tick < state.tick) {
+                    history.push_back(AimFrame {
+                        tick: state.tick,
+                        pitch,
+                        yaw,
+                        scoped: player_state.scoped,
+                    });
+                }
+                while history.len() > HISTORY_TICKS {
+                    history.pop_front();
+                }
+            }
+        }
+    }
+
+    fn check_headshot(&self, hit: &HitEvent, class: Option<u8>, yaw_precision: f32) -> Option<Detection> {
+        let history = self.history.get(&hit.attacker)?;
+        let mut frames: Vec<&AimFrame> = history.iter().filter(|frame| frame.tick <= hit.tick).collect();
+        frames.drain(..frames.len().saturating_sub(FLICK_WINDOW + 2));
+        if frames.len() < 2 {
+            return None;
+        }
+
+        let speeds: Vec<(u32, f32)> = frames.windows(2)
+            .filter_map(|pair| Self::angular_speed(pair[0], pair[1]).map(|speed| (pair[1].tick, speed)))
+            .collect();
+        let (flick_tick, flick_speed) = speeds.iter()
+            .copied()
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let shot_speed = speeds.last().map(|(_, speed)| *speed).unwrap_or(0.0);
+
+        let flicked = flick_speed > FLICK_THRESHOLD;
+        // Networked angles jitter by up to one step even when the crosshair is still
+        let settled = flick_tick < hit.tick && shot_speed < SETTLED_THRESHOLD + yaw_precision;
+
+        // Zoom state is only available when the demo recorded it
+        let scoped_at_shot = frames.last().and_then(|frame| frame.scoped);
+        let unscoped_tick = frames.iter()
+            .filter(|frame| frame.scoped == Some(false))
+            .map(|frame| frame.tick)
+            .max();
+        // A sample can't place the unscope any closer to the shot than the gap it covers
+        let shot_gap = frames[frames.len() - 1].tick - frames[frames.len() - 2].tick;
+        let instant_scope = scoped_at_shot == Some(true)
+            && unscoped_tick.map_or(false, |tick| hit.tick - tick <= INSTANT_SCOPE_WINDOW.max(shot_gap))
+            && flicked;
+
+        let kind = if instant_scope {
+            "instant_scope_flick"
+        } else if flicked && settled {
+            "flick_headshot"
+        } else {
+            return None;
+        };
+
+        Some(Detection {
+            tick: hit.tick,
+            algorithm: self.algorithm_name().to_string(),
+            player: hit.attacker,
+            data: json!({
+                "type": kind,
+                "player_name": self.player_names.get(&hit.attacker).cloned().unwrap_or_default(),
+                "victim": hit.victim,
+                "victim_name": self.player_names.get(&hit.victim).cloned().unwrap_or_default(),
+                "class": class.map(class_name),
+                "weapon": hit.weapon,
+                "kill": hit.kill,
+                "flick_tick": flick_tick,
+                "flick_speed": flick_speed,
+                "shot_speed": shot_speed,
+                "unscoped_tick": unscoped_tick,
+            }),
+            timestamp: None,
+        })
+    }
+}
+
+impl CheatAlgorithm for SniperAnalyzer {
+    fn algorithm_name(&self) -> &str {
+        "sniper_analyzer"
+    }
+
+    fn handled_messages(&self) -> Result<Vec<MessageType>, bool> {
+        Ok(vec![MessageType::GameEvent])
+    }
+
+    fn on_tick(&mut self, state: &CheatAnalyserState, _parser_state: &ParserState) -> Result<Vec<Detection>, Error> {
+        self.record_frames(state);
+
+        let mut detections = Vec::new();
+        // A kill usually arrives together with the hurt event of the same shot
+        let mut seen = HashSet::new();
+
+        for hit in &state.hit_events {
+            if hit.attacker == hit.victim {
+                continue;
+            }
+            let class = state.player_states.get(&hit.attacker).and_then(|player| player.class);
+
+            if hit.kill {
+                if let Some(class) = class {
+                    let kills = self.class_kills.entry((hit.attacker, class)).or_default();
+                    kills.kills += 1;
+                    if hit.headshot {
+                        kills.headshots += 1;
+                        kills.first_headshot_tick.get_or_insert(hit.tick);
+                    }
+                }
+            }
+
+            let is_sniper_shot = class.map_or(true, |class| class == SNIPER_CLASS || class == SPY_CLASS);
+            if hit.headshot && is_sniper_shot && seen.insert((hit.attacker, hit.victim, hit.tick)) {
+                if let Some(detection) = self.check_headshot(hit, class, state.yaw_precision(hit.attacker)) {
+                    detections.push(detection);
+                }
+            }
+        }
+
+        Ok(detections)
+    }
+
+    fn on_message(&mut self, _message: &Message, _state: &CheatAnalyserState, _parser_state: &ParserState, _tick: DemoTick) -> Result<Vec<Detection>, Error> {
+        Ok(vec![])
+    }
+
+    fn finish(&mut self) -> Result<Vec<Detection>, Error> {
+        let mut detections = Vec::new();
+
+        // Lobby average per class, to put each player's rate into context
+        let mut lobby: HashMap<u8, (usize, usize)> = HashMap::new();
+        for ((_, class), kills) in &self.class_kills {
+            let entry = lobby.entry(*class).or_default();
+            entry.0 += kills.kills;
+            entry.1 += kills.headshots;
+        }
+
+        for ((player_id, class), kills) in &self.class_kills {
+            let limit = match *class {
+                SNIPER_CLASS => MAX_SNIPER_HEADSHOT_RATE,
+                SPY_CLASS => MAX_SPY_HEADSHOT_RATE,
+                _ => continue,
+            };
+            if kills.kills < MIN_CLASS_KILLS {
+                continue;
+            }
+
+            let rate = kills.headshots as f32 / kills.kills as f32;
+            if rate <= limit {
+                continue;
+            }
+
+            let (lobby_kills, lobby_headshots) = lobby.get(class).copied().unwrap_or((0, 0));
+            detections.push(Detection {
+                tick: kills.first_headshot_tick.unwrap_or(0),
+                algorithm: self.algorithm_name().to_string(),
+                player: *player_id,
+                data: json!({
+                    "type": "abnormal_headshot_rate",
+                    "player_name": self.player_names.get(player_id).cloned().unwrap_or_default(),
+                    "class": class_name(*class),
+                    "kills": kills.kills,
+                    "headshots": kills.headshots,
+                    "headshot_rate": rate,
+                    "limit": limit,
+                    "lobby_headshot_rate": if lobby_kills > 0 {
+                        lobby_headshots as f32 / lobby_kills as f32
+                    } else {
+                        0.0
+                    },
+                }),
+                timestamp: None,
+            });
+        }
+
+        self.history.clear();
+        Ok(detections)
+    }
+}
@@ -0,0 +1,461 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use anyhow::{anyhow, Error};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::corpus::{ExpectedDetection, Labels};
+use super::math::calculate_angle_delta;
+use super::DemoKind;
+
+// TF2 runs at 66.67 ticks per second
+const TICK_INTERVAL: f32 = 0.015;
+// m_yaw/m_pitch, one mouse count turns the view by sensitivity * this
+const MOUSE_STEP: f32 = 0.022;
+// Human pitch stays well inside the clamp
+const MAX_HUMAN_PITCH: f32 = 60.0;
+// Default length of injected cheats that last more than a tick
+const DEFAULT_CHEAT_DURATION: u32 = 40;
+// Yaw turned per tick by a spinbot
+const SPIN_SPEED: f32 = 50.0;
+// Degrees per tick a smoothed aimbot moves towards its target
+const SMOOTH_STEP: f32 = 1.5;
+// Ticks a generated label may be off from the detector's tick
+const LABEL_TOLERANCE: u32 = 8;
+
+const FIRST_STEAMID: u64 = 76561198100000001;
+
+/// Cheat signatures the generator can inject
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheatKind {
+    Psilent,  // one tick at the target, then back
+    Snap,     // instant large flick that stays on target
+    AntiAim,  // spinning yaw with a fake pitch
+    OobPitch, // pitch past the +-89 clamp
+    Smoothing, // constant-step aim instead of mouse input
+}
+
+impl CheatKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "psilent" => Some(CheatKind::Psilent),
+            "snap" => Some(CheatKind::Snap),
+            "antiaim" | "anti_aim" => Some(CheatKind::AntiAim),
+            "oob" | "oob_pitch" => Some(CheatKind::OobPitch),
+            "smooth" | "smoothing" => Some(CheatKind::Smoothing),
+            _ => None,
+        }
+    }
+}
+
+/// One cheat placed in the trace
+#[derive(Clone, Copy, Debug)]
+pub struct CheatInjection {
+    pub kind: CheatKind,
+    pub player: usize,
+    pub tick: u32,
+    pub duration: u32,
+}
+
+impl CheatInjection {
+    /// Parse `kind:player:tick[:duration]`, players are numbered from 1
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        if parts.len() < 3 {
+            return Err(anyhow!("Expected kind:player:tick[:duration], got {}", spec));
+        }
+        let kind = CheatKind::from_name(parts[0]).ok_or_else(|| anyhow!("Unknown cheat kind {}", parts[0]))?;
+        let player: usize = parts[1].parse()?;
+        if player == 0 {
+            return Err(anyhow!("Players are numbered from 1"));
+        }
+        let tick: u32 = parts[2].parse()?;
+        let duration = match parts.get(3) {
+            Some(duration) => duration.parse()?,
+            None if kind == CheatKind::Smoothing => 1000,
+            None => DEFAULT_CHEAT_DURATION,
+        };
+        Ok(Self { kind, player: player - 1, tick, duration })
+    }
+}
+
+pub struct TraceConfig {
+    pub players: usize,
+    pub ticks: u32,
+    pub seed: u64,
+    pub cheats: Vec<CheatInjection>,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            players: 6,
+            ticks: 4000,
+            seed: 1,
+            cheats: Vec::new(),
+        }
+    }
+}
+
+impl TraceConfig {
+    /// Parse `--players N --ticks N --seed N --inject kind:player:tick[:duration]...`
+    pub fn from_args(args: &[String]) -> Result<Self, Error> {
+        let mut config = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or_else(|| anyhow!("Missing value for {}", arg))?;
+            match arg.as_str() {
+                "--players" => config.players = value.parse()?,
+                "--ticks" => config.ticks = value.parse()?,
+                "--seed" => config.seed = value.parse()?,
+                "--inject" => config.cheats.push(CheatInjection::parse(value)?),
+                _ => return Err(anyhow!("Unknown option {}", arg)),
+            }
+        }
+        if let Some(cheat) = config.cheats.iter().find(|cheat| cheat.player >= config.players) {
+            return Err(anyhow!("Cheat injected for player {} but only {} players", cheat.player + 1, config.players));
+        }
+        Ok(config)
+    }
+}
+
+/// One row of the generated trace
+pub struct TraceRow {
+    pub tick: u32,
+    pub player: usize,
+    pub position: (f32, f32, f32),
+    pub yaw: f32,
+    pub pitch: f32,
+    pub class: u8,
+}
+
+pub struct Trace {
+    pub rows: Vec<TraceRow>,
+    pub players: Vec<(u64, String)>,
+    pub labels: Labels,
+}
+
+/// A flick from the current aim towards a new target
+struct Flick {
+    from: (f32, f32),
+    to: (f32, f32),
+    start: u32,
+    length: u32,
+}
+
+/// Simulated human aim: idle tremor, bell-shaped flicks with over/undershoot
+/// between targets, everything quantised to the player's mouse step
+struct HumanAim {
+    yaw: f32,
+    pitch: f32,
+    step: f32,
+    residual: (f32, f32), // sub-count movement carried to the next tick
+    flick: Option<Flick>,
+    smooth_target: Option<(f32, f32)>, // yaw, pitch the smoothed aimbot is heading for
+    next_flick: u32,
+    position: (f32, f32, f32),
+    heading: f32,
+    speed: f32,
+}
+
+impl HumanAim {
+    fn new(rng: &mut StdRng) -> Self {
+        let sensitivity = rng.gen_range(1.5..4.0);
+        Self {
+            yaw: rng.gen_range(0.0..360.0),
+            pitch: rng.gen_range(-10.0..10.0),
+            step: sensitivity * MOUSE_STEP,
+            residual: (0.0, 0.0),
+            flick: None,
+            smooth_target: None,
+            next_flick: rng.gen_range(20..120),
+            position: (rng.gen_range(-2000.0..2000.0), rng.gen_range(-2000.0..2000.0), 0.0),
+            heading: rng.gen_range(0.0..360.0),
+            speed: rng.gen_range(180.0..300.0),
+        }
+    }
+
+    /// Next thing to look at, as a yaw and pitch offset from the current aim
+    fn pick_target(&self, rng: &mut StdRng) -> (f32, f32) {
+        let yaw_offset = rng.gen_range(10.0..90.0) * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        let target_pitch = (self.pitch + rng.gen_range(-25.0..25.0)).clamp(-MAX_HUMAN_PITCH, MAX_HUMAN_PITCH);
+        (yaw_offset, target_pitch - self.pitch)
+    }
+
+    /// Angle change the player wants this tick, before quantisation
+    fn desired_delta(&mut self, tick: u32, rng: &mut StdRng) -> (f32, f32) {
+        if self.flick.is_none() && tick >= self.next_flick {
+            let (yaw_offset, pitch_offset) = self.pick_target(rng);
+            // Humans overshoot or undershoot by a few percent and correct afterwards
+            let accuracy = rng.gen_range(0.93..1.06);
+            self.flick = Some(Flick {
+                from: (self.yaw, self.pitch),
+                to: (self.yaw + yaw_offset * accuracy, self.pitch + pitch_offset * accuracy),
+                start: tick,
+                length: rng.gen_range(8..25),
+            });
+        }
+
+        let mut delta = match &self.flick {
+            Some(flick) => {
+                // Minimum-jerk profile, position follows 10t^3 - 15t^4 + 6t^5
+                let progress = |t: f32| 10.0 * t.powi(3) - 15.0 * t.powi(4) + 6.0 * t.powi(5);
+                let elapsed = (tick - flick.start) as f32;
+                let length = flick.length as f32;
+                let share = progress(((elapsed + 1.0) / length).min(1.0)) - progress((elapsed / length).min(1.0));
+                let yaw = (flick.to.0 - flick.from.0) * share;
+                let pitch = (flick.to.1 - flick.from.1) * share;
+                (pitch, yaw)
+            }
+            None => (0.0, 0.0),
+        };
+
+        if let Some(flick) = &self.flick {
+            if tick + 1 >= flick.start + flick.length {
+                self.flick = None;
+                self.next_flick = tick + rng.gen_range(40..150);
+            }
+        }
+
+        // Hand tremor and tracking corrections
+        if rng.gen_bool(0.35) {
+            delta.0 += rng.gen_range(-2.0..2.0) * self.step;
+            delta.1 += rng.gen_range(-3.0..3.0) * self.step;
+        }
+        delta
+    }
+
+    /// Apply a delta in whole mouse counts, keeping the remainder for later ticks
+    fn apply_mouse(&mut self, delta: (f32, f32)) {
+        let pitch = delta.0 + self.residual.0;
+        let yaw = delta.1 + self.residual.1;
+        let pitch_counts = (pitch / self.step).round();
+        let yaw_counts = (yaw / self.step).round();
+        self.residual = (pitch - pitch_counts * self.step, yaw - yaw_counts * self.step);
+        self.pitch = (self.pitch + pitch_counts * self.step).clamp(-MAX_HUMAN_PITCH, MAX_HUMAN_PITCH);
+        self.yaw = (self.yaw + yaw_counts * self.step).rem_euclid(360.0);
+    }
+
+    /// Aimbot smoothing: straight at the next target with a constant step and no tremor
+    fn smoothed_step(&mut self, tick: u32, rng: &mut StdRng) {
+        if self.smooth_target.is_none() && tick >= self.next_flick {
+            let (yaw_offset, pitch_offset) = self.pick_target(rng);
+            self.smooth_target = Some((self.yaw + yaw_offset, self.pitch + pitch_offset));
+        }
+
+        if let Some((target_yaw, target_pitch)) = self.smooth_target {
+            let yaw = calculate_angle_delta(target_yaw, self.yaw);
+            let pitch = target_pitch - self.pitch;
+            let distance = (yaw * yaw + pitch * pitch).sqrt();
+            if distance <= SMOOTH_STEP {
+                self.yaw = target_yaw.rem_euclid(360.0);
+                self.pitch = target_pitch;
+                self.smooth_target = None;
+                self.next_flick = tick + rng.gen_range(40..150);
+            } else {
+                let scale = SMOOTH_STEP / distance;
+                self.yaw = (self.yaw + yaw * scale).rem_euclid(360.0);
+                self.pitch += pitch * scale;
+            }
+        }
+    }
+
+    fn walk(&mut self, rng: &mut StdRng) {
+        self.heading = (self.heading + rng.gen_range(-2.0..2.0)).rem_euclid(360.0);
+        let distance = self.speed * TICK_INTERVAL;
+        let heading = self.heading.to_radians();
+        self.position.0 += heading.cos() * distance;
+        self.position.1 += heading.sin() * distance;
+    }
+}
+
+/// Generate a trace of human players with the configured cheats injected
+pub fn generate(config: &TraceConfig) -> Trace {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut aims: Vec<HumanAim> = (0..config.players).map(|_| HumanAim::new(&mut rng)).collect();
+    let classes: Vec<u8> = (0..config.players).map(|_| rng.gen_range(1..=9)).collect();
+    let players: Vec<(u64, String)> = (0..config.players)
+        .map(|i| (FIRST_STEAMID + i as u64, format!("player{}", i + 1)))
+        .collect();
+
+    let mut rows = Vec::new();
+    for tick in 1..=config.ticks {
+        for (player, aim) in aims.iter_mut().enumerate() {
+            let active = config.cheats.iter()
+                .find(|cheat| cheat.player == player && tick >= cheat.tick && tick < cheat.tick + cheat.duration.max(1));
+
+            if active.map_or(false, |cheat| cheat.kind == CheatKind::Smoothing) {
+                aim.smoothed_step(tick, &mut rng);
+            } else {
+                let delta = aim.desired_delta(tick, &mut rng);
+                aim.apply_mouse(delta);
+            }
+            aim.walk(&mut rng);
+
+            // Cheats that override what is sent without touching the real aim
+            let (mut yaw, mut pitch) = (aim.yaw, aim.pitch);
+            if let Some(cheat) = active {
+                let elapsed = tick - cheat.tick;
+                match cheat.kind {
+                    CheatKind::Psilent if elapsed == 0 => {
+                        yaw = (yaw + 45.0).rem_euclid(360.0);
+                        pitch = (pitch + 8.0).clamp(-89.0, 89.0);
+                    }
+                    CheatKind::Snap if elapsed == 0 => {
+                        aim.yaw = (aim.yaw + 70.0).rem_euclid(360.0);
+                        yaw = aim.yaw;
+                    }
+                    CheatKind::AntiAim => {
+                        yaw = (yaw + SPIN_SPEED * (elapsed + 1) as f32).rem_euclid(360.0);
+                        pitch = 89.0;
+                    }
+                    CheatKind::OobPitch => {
+                        pitch = 91.0;
+                    }
+                    _ => {}
+                }
+            }
+
+            rows.push(TraceRow {
+                tick,
+                player,
+                position: aim.position,
+                yaw,
+                pitch,
+                class: classes[player],
+            });
+        }
+    }
+
+    Trace {
+        rows,
+        labels: labels_for(config, &players),
+        players,
+    }
+}
+
+/// What the detectors are expected to report for each injected cheat
+fn labels_for(config: &TraceConfig, players: &[(u64, String)]) -> Labels {
+    let mut expected = Vec::new();
+    let label = |algorithm: &str, player: usize, tick: u32, tick_end: Option<u32>, kind: &str| ExpectedDetection {
+        algorithm: algorithm.to_string(),
+        player: players[player].0,
+        tick,
+        tick_end,
+        kind: Some(kind.to_string()),
+    };
+
+    for cheat in &config.cheats {
+        let end = cheat.tick + cheat.duration.max(1);
+        match cheat.kind {
+            // Out to the target and back again are both flagged
+            CheatKind::Psilent => expected.push(label("viewangles_analyzer", cheat.player, cheat.tick, Some(cheat.tick + 1), "suspicious_angle_change")),
+            CheatKind::Snap => expected.push(label("viewangles_analyzer", cheat.player, cheat.tick, None, "suspicious_angle_change")),
+            CheatKind::AntiAim => expected.push(label("viewangles_analyzer", cheat.player, cheat.tick, Some(end), "suspicious_angle_change")),
+            CheatKind::OobPitch => {
+                expected.push(label("viewangles_analyzer", cheat.player, cheat.tick, Some(end - 1), "out_of_bounds_pitch"));
+                expected.push(label("viewangles_analyzer", cheat.player, cheat.tick, Some(end), "suspicious_angle_change"));
+            }
+            CheatKind::Smoothing => expected.push(label("smoothness_analyzer", cheat.player, cheat.tick, Some(end), "unnatural_smoothness")),
+        }
+    }
+
+    Labels {
+        description: format!(
+            "Generated: {} players, {} ticks, seed {}, {} injected cheats",
+            config.players, config.ticks, config.seed, config.cheats.len()
+        ),
+        demo_kind: Some(DemoKind::Pov),
+        recorder: None,
+        algorithms: vec!["viewangles_analyzer".to_string(), "smoothness_analyzer".to_string()],
+        tolerance: LABEL_TOLERANCE,
+        expected,
+    }
+}
+
+/// Write the trace as a corpus fixture: `trace.csv` in the viewangle CSV format plus `labels.json`
+pub fn write_fixture(trace: &Trace, dir: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut file = File::create(dir.join("trace.csv"))?;
+    writeln!(file, "tick,player_id,player_name,origin_x,origin_y,origin_z,viewangle,pitchangle,va_delta,pa_delta,class,scoped")?;
+
+    let mut previous: Vec<Option<(f32, f32)>> = vec![None; trace.players.len()];
+    for row in &trace.rows {
+        let (player_id, name) = &trace.players[row.player];
+        let (va_delta, pa_delta) = match previous[row.player] {
+            Some((prev_yaw, prev_pitch)) => {
+                (format!("{:.2}", calculate_angle_delta(row.yaw, prev_yaw)), format!("{:.2}", row.pitch - prev_pitch))
+            }
+            None => ("NaN".to_string(), "NaN".to_string()),
+        };
+        previous[row.player] = Some((row.yaw, row.pitch));
+
+        writeln!(
+            file,
+            "{},{},{},{:.2},{:.2},{:.2},{:.4},{:.4},{},{},{},",
+            row.tick, player_id, name,
+            row.position.0, row.position.1, row.position.2,
+            row.yaw, row.pitch, va_delta, pa_delta, row.class,
+        )?;
+    }
+
+    std::fs::write(dir.join("labels.json"), serde_json::to_string_pretty(&trace.labels)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::corpus::{corpus_algorithms, run_fixture, score_fixture, Fixture, FixtureReport, FixtureSource};
+
+    /// Write a generated trace to its own temp folder and grade it like a corpus fixture
+    fn generate_and_grade(name: &str, config: &TraceConfig) -> FixtureReport {
+        let dir = std::env::temp_dir().join(format!("tracegen_{}_{}", name, std::process::id()));
+        let trace = generate(config);
+        write_fixture(&trace, &dir).expect("fixture should be written");
+        let fixture = Fixture {
+            name: name.to_string(),
+            source: FixtureSource::Trace(dir.join("trace.csv")),
+            events: None,
+            map: None,
+            labels: trace.labels.clone(),
+        };
+        let detections = run_fixture(&fixture, corpus_algorithms());
+        let _ = std::fs::remove_dir_all(&dir);
+        score_fixture(name, &fixture.labels, &detections.expect("fixture should replay"))
+    }
+
+    fn config(seed: u64, cheats: &[&str]) -> TraceConfig {
+        TraceConfig {
+            players: 6,
+            ticks: 3000,
+            seed,
+            cheats: cheats.iter().map(|spec| CheatInjection::parse(spec).expect("valid cheat")).collect(),
+        }
+    }
+
+    #[test]
+    fn injected_cheats_are_detected() {
+        for seed in [3, 11] {
+            let report = generate_and_grade(
+                &format!("injected_{}", seed),
+                &config(seed, &["psilent:1:300", "snap:2:600", "antiaim:3:900", "oob:4:1200:20", "smoothing:5:1200:1800"]),
+            );
+            assert!(report.missed.is_empty(), "seed {}: missed {:?}", seed, report.missed);
+        }
+    }
+
+    #[test]
+    fn clean_trace_is_not_flagged() {
+        for seed in [3, 11] {
+            let report = generate_and_grade(&format!("clean_{}", seed), &config(seed, &[]));
+            assert!(
+                report.unexpected.is_empty(),
+                "seed {}: unexpected {:?}",
+                seed,
+                report.unexpected.iter().map(|d| (&d.algorithm, d.player, d.tick)).collect::<Vec<_>>()
+            );
+        }
+    }
+}
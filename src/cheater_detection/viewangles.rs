@@ -9,18 +9,27 @@ use tf_demo_parser::{
     MessageType, ParserState,
 };
 
-use super::{CheatAlgorithm, CheatAnalyserState, Detection};
+use super::{CheatAlgorithm, CheatAnalyserState, Detection, DEFAULT_TICK_INTERVAL};
+use super::math::{calculate_angle_delta, sample_gap};
 
 /// Structure to record viewangle data over time
-#[derive(Debug)]
-struct ViewAngleRecord {
-    tick: u32,
-    pitch: f32,
-    yaw: f32,
-    roll: f32,
-    position: Option<(f32, f32, f32)>,  // Optional position data
-    va_delta: Option<f32>,  // Yaw delta from previous tick
-    pa_delta: Option<f32>,  // Pitch delta from previous tick
+#[derive(Debug, Clone)]
+pub struct ViewAngleRecord {
+    pub tick: u32,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub roll: f32,
+    pub position: Option<(f32, f32, f32)>,  // Optional position data
+    pub va_delta: Option<f32>,  // Yaw delta from previous tick
+    pub pa_delta: Option<f32>,  // Pitch delta from previous tick
+}
+
+/// Everything the analyzer recorded, handed to the timeline plot and radar
+pub struct ViewAngleRecording {
+    pub viewangles: HashMap<u64, Vec<ViewAngleRecord>>,
+    pub player_names: HashMap<u64, String>,
+    pub player_teams: HashMap<u64, u8>, // last team seen, 2 = RED, 3 = BLU
+    pub tick_interval: f32, // seconds per tick, from the demo header
 }
 
 pub struct ViewAnglesAnalyzer {
@@ -30,7 +39,10 @@ pub struct ViewAnglesAnalyzer {
     player_viewangles: HashMap<u64, Vec<ViewAngleRecord>>,
     // Map player IDs to names for better labeling
     player_names: HashMap<u64, String>,
+    player_teams: HashMap<u64, u8>,
+    tick_interval: f32,
     output_path: Option<PathBuf>,
+    write_output: bool,
     debug_mode: bool,
 }
 
@@ -41,7 +53,10 @@ impl ViewAnglesAnalyzer {
             detections: Vec::new(),
             player_viewangles: HashMap::new(),
             player_names: HashMap::new(),
+            player_teams: HashMap::new(),
+            tick_interval: DEFAULT_TICK_INTERVAL,
             output_path: None,
+            write_output: true,
             debug_mode: false,  // Disable debug mode by default
         }
     }
@@ -50,18 +65,23 @@ impl ViewAnglesAnalyzer {
     pub fn set_output_path(&mut self, path: PathBuf) {
         self.output_path = Some(path);
     }
+
+    /// Whether `finish` writes the viewangles file, off when only the detections matter
+    pub fn set_write_output(&mut self, write: bool) {
+        self.write_output = write;
+    }
     
-    /// Calculate angle delta properly handling wraparound at 360 degrees
-    fn calculate_angle_delta(&self, current: f32, previous: f32) -> f32 {
-        let diff = (current - previous).rem_euclid(360.0);
-        if diff > 180.0 {
-            diff - 360.0
-        } else {
-            diff
+    /// Take the recorded viewangles and player names out of the analyzer
+    pub fn take_recording(&mut self) -> ViewAngleRecording {
+        ViewAngleRecording {
+            viewangles: std::mem::take(&mut self.player_viewangles),
+            player_names: std::mem::take(&mut self.player_names),
+            player_teams: std::mem::take(&mut self.player_teams),
+            tick_interval: self.tick_interval,
         }
     }
     
-    fn check_angle_change(&mut self, player_id: u64, current: (f32, f32, f32), position: Option<(f32, f32, f32)>, tick: u32) {
+    fn check_angle_change(&mut self, player_id: u64, current: (f32, f32, f32), position: Option<(f32, f32, f32)>, tick: u32, pitch_precision: f32) {
         // Record the viewangle data
         let (curr_pitch, curr_yaw, curr_roll) = current;
         
@@ -75,7 +95,7 @@ impl ViewAnglesAnalyzer {
             let (prev_pitch, prev_yaw, _) = *previous;
             
             // Calculate proper angle deltas accounting for wraparound
-            let yaw_delta = self.calculate_angle_delta(curr_yaw, prev_yaw);
+            let yaw_delta = calculate_angle_delta(curr_yaw, prev_yaw);
             let pitch_delta = curr_pitch - prev_pitch;  // Pitch doesn't wrap around
             
             (Some(yaw_delta), Some(pitch_delta))
@@ -83,6 +103,10 @@ impl ViewAnglesAnalyzer {
             (None, None)
         };
         
+        let previous_tick = self.player_viewangles.get(&player_id)
+            .and_then(|records| records.last())
+            .map(|record| record.tick);
+
         // Store this viewangle record for the player
         self.player_viewangles
             .entry(player_id)
@@ -107,7 +131,11 @@ impl ViewAnglesAnalyzer {
             const SUSPICIOUS_YAW_CHANGE: f32 = 30.0;    // Large horizontal flick
             const SUSPICIOUS_PITCH_CHANGE: f32 = 20.0;  // Large vertical flick
             
-            if yaw_delta_abs > SUSPICIOUS_YAW_CHANGE || pitch_delta_abs > SUSPICIOUS_PITCH_CHANGE {
+            // SourceTV records every few ticks, the change is spread over the gap
+            let is_flick = previous_tick.and_then(|previous| sample_gap(previous, tick)).map_or(false, |gap| {
+                yaw_delta_abs / gap as f32 > SUSPICIOUS_YAW_CHANGE || pitch_delta_abs / gap as f32 > SUSPICIOUS_PITCH_CHANGE
+            });
+            if is_flick {
                 self.detections.push(Detection {
                     tick,
                     algorithm: self.algorithm_name().to_string(),
@@ -126,12 +154,15 @@ impl ViewAnglesAnalyzer {
                         },
                         "magnitude": (pitch_delta_abs.powi(2) + yaw_delta_abs.powi(2)).sqrt(),
                     }),
+                    timestamp: None,
                 });
             }
             
             // Detect out-of-bounds pitch values
             const MAX_PITCH_ANGLE: f32 = 89.8;  // Maximum normal pitch angle in TF2
-            if curr_pitch.abs() > MAX_PITCH_ANGLE {
+            // Networked angles can round a legit 89 degrees past the limit
+            let pitch_limit = MAX_PITCH_ANGLE + pitch_precision;
+            if curr_pitch.abs() > pitch_limit {
                 self.detections.push(Detection {
                     tick,
                     algorithm: self.algorithm_name().to_string(),
@@ -139,9 +170,10 @@ impl ViewAnglesAnalyzer {
                     data: json!({
                         "type": "out_of_bounds_pitch",
                         "pitch": curr_pitch,
-                        "limit": MAX_PITCH_ANGLE,
-                        "excess": curr_pitch.abs() - MAX_PITCH_ANGLE,
+                        "limit": pitch_limit,
+                        "excess": curr_pitch.abs() - pitch_limit,
                     }),
+                    timestamp: None,
                 });
             }
         }
@@ -226,6 +258,7 @@ impl CheatAlgorithm for ViewAnglesAnalyzer {
     }
 
     fn on_tick(&mut self, state: &CheatAnalyserState, _parser_state: &ParserState) -> Result<Vec<Detection>, Error> {
+        self.tick_interval = state.tick_interval;
         // Process each player's viewangles
         for (player_id, player_state) in &state.player_states {
             // Store player name for better labeling
@@ -235,13 +268,16 @@ impl CheatAlgorithm for ViewAnglesAnalyzer {
                     println!("Found player name: {} (ID: {})", player_state.name, player_id);
                 }
             }
+            if let Some(team) = player_state.team {
+                self.player_teams.insert(*player_id, team);
+            }
             
             // Process viewangles and position if available
             if let Some(angles) = player_state.viewangles {
                 if self.debug_mode {
                     println!("Processing angles for player {}: {:?}", player_id, angles);
                 }
-                self.check_angle_change(*player_id, angles, player_state.position, state.tick);
+                self.check_angle_change(*player_id, angles, player_state.position, state.tick, state.pitch_precision(*player_id));
             }
         }
 
@@ -261,9 +297,11 @@ impl CheatAlgorithm for ViewAnglesAnalyzer {
         println!("Total angle records: {}", self.player_viewangles.values().map(|v| v.len()).sum::<usize>());
         
         // Write all collected viewangles to file
-        match self.write_viewangles_to_file() {
-            Ok(path) => println!("Viewangles data written to: {}", path.display()),
-            Err(e) => eprintln!("Failed to write viewangles data to file: {}", e),
+        if self.write_output {
+            match self.write_viewangles_to_file() {
+                Ok(path) => println!("Viewangles data written to: {}", path.display()),
+                Err(e) => eprintln!("Failed to write viewangles data to file: {}", e),
+            }
         }
         
         self.previous_angles.clear();
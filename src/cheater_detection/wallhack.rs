@@ -0,0 +1,341 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use anyhow::Error;
+use serde_json::json;
+use tf_demo_parser::{
+    demo::{data::DemoTick, message::Message},
+    MessageType, ParserState,
+};
+
+use super::backtrack::{aim_direction, dot, length, sub, EYE_HEIGHT, HULL_HEIGHT, HULL_RADIUS};
+use super::math::Baseline;
+use super::{CheatAlgorithm, CheatAnalyserState, Detection, PlayerState};
+
+// Crosshair within this many degrees of an enemy's chest counts as on target
+const ON_TARGET_ANGLE: f32 = 4.0;
+// Enemies this far away can't be heard, so tracking them needs a visual
+const MIN_HIDDEN_DISTANCE: f32 = 1024.0;
+// Vertical and horizontal separation that puts an enemy on another floor
+const FLOOR_SEPARATION: f32 = 160.0;
+const MIN_FLOOR_DISTANCE: f32 = 256.0;
+// An enemy that fired or got hurt this recently has given away their position
+const CUE_TICKS: u32 = 132;
+// Tracking shorter than this is a crosshair passing over someone
+const MIN_TRACK_TICKS: u32 = 33;
+// The target has to move across the view while tracked, otherwise it's an angle being held
+const MIN_BEARING_CHANGE: f32 = 10.0;
+// A hit on the target this soon after the tracking means they were visible by then
+const ENGAGE_TICKS: u32 = 66;
+// Tracking that long before the first shot is pre-aim rather than a reaction
+const PREAIM_TICKS: u32 = 66;
+// Ticks with a hidden enemy around needed before a player's ratio is trusted
+const MIN_ELIGIBLE_TICKS: u32 = 1000;
+// Counted segments and ratio needed before a player is reported
+const MIN_SEGMENTS: usize = 3;
+const MIN_TRACKING_RATIO: f32 = 0.05;
+// A lobby baseline needs at least this many players with usable ratios
+const MIN_LOBBY_PLAYERS: usize = 3;
+const Z_SCORE_THRESHOLD: f32 = 3.0;
+
+const RED_TEAM: u8 = 2;
+const BLU_TEAM: u8 = 3;
+
+/// Crosshair following one enemy over consecutive ticks
+struct TrackSegment {
+    start: u32,
+    last: u32,
+    start_direction: (f32, f32, f32),
+    bearing_change: f32,
+    distance_sum: f32,
+}
+
+/// A finished tracking segment
+struct TrackedTarget {
+    target: u64,
+    start: u32,
+    end: u32,
+    bearing_change: f32,
+    mean_distance: f32,
+}
+
+#[derive(Default)]
+struct TrackingStats {
+    eligible_ticks: u32,
+    segments: Vec<TrackedTarget>,
+}
+
+/// Looks for crosshairs that follow enemies the player has no way of seeing
+/// or hearing. Hidden means no line of sight when the map is loaded and is
+/// otherwise approximated as far away or on another floor, either way the
+/// enemy mustn't have fired or been hit recently. A
+/// segment counts when the crosshair stays on such an enemy while they move
+/// across the view, and either never ends in a hit (tracked through a wall)
+/// or was held long before the first shot (pre-aimed around a corner). The
+/// share of hidden-enemy time spent tracking is compared to the lobby.
+pub struct WallhackAnalyzer {
+    open_segments: HashMap<(u64, u64), TrackSegment>,
+    stats: HashMap<u64, TrackingStats>,
+    // Last tick each player made noise or was hurt
+    last_cue: HashMap<u64, u32>,
+    // Ticks each attacker hit each victim
+    hits: HashMap<(u64, u64), Vec<u32>>,
+    player_names: HashMap<u64, String>,
+    uses_map: bool,
+}
+
+impl WallhackAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            open_segments: HashMap::new(),
+            stats: HashMap::new(),
+            last_cue: HashMap::new(),
+            hits: HashMap::new(),
+            player_names: HashMap::new(),
+            uses_map: false,
+        }
+    }
+
+    fn is_enemy(player: &PlayerState, other: &PlayerState) -> bool {
+        match (player.team, other.team) {
+            (Some(a), Some(b)) => a != b && (a == RED_TEAM || a == BLU_TEAM) && (b == RED_TEAM || b == BLU_TEAM),
+            _ => false,
+        }
+    }
+
+    /// Enemy can't be seen or heard, using the map when it's loaded and
+    /// distance and floors when it isn't
+    fn is_hidden(&self, state: &CheatAnalyserState, eye: (f32, f32, f32), to_target: (f32, f32, f32), target: u64) -> bool {
+        let cued = self.last_cue.get(&target).map_or(false, |cue| state.tick.saturating_sub(*cue) <= CUE_TICKS);
+        if cued {
+            return false;
+        }
+
+        let target_point = (eye.0 + to_target.0, eye.1 + to_target.1, eye.2 + to_target.2);
+        match state.is_visible(eye, target_point) {
+            Some(visible) => !visible,
+            None => {
+                let horizontal = (to_target.0 * to_target.0 + to_target.1 * to_target.1).sqrt();
+                let other_floor = to_target.2.abs() >= FLOOR_SEPARATION && horizontal >= MIN_FLOOR_DISTANCE;
+                length(to_target) >= MIN_HIDDEN_DISTANCE || other_floor
+            }
+        }
+    }
+
+    fn close_segment(&mut self, tracker: u64, target: u64, segment: TrackSegment) {
+        let length = segment.last - segment.start + 1;
+        if length < MIN_TRACK_TICKS || segment.bearing_change < MIN_BEARING_CHANGE {
+            return;
+        }
+        self.stats.entry(tracker).or_default().segments.push(TrackedTarget {
+            target,
+            start: segment.start,
+            end: segment.last,
+            bearing_change: segment.bearing_change,
+            mean_distance: segment.distance_sum / length as f32,
+        });
+    }
+
+    /// First hit on the target from the start of the segment until shortly after it
+    fn engaged_at(&self, tracker: u64, segment: &TrackedTarget) -> Option<u32> {
+        self.hits.get(&(tracker, segment.target))?
+            .iter()
+            .copied()
+            .filter(|tick| *tick >= segment.start && *tick <= segment.end + ENGAGE_TICKS)
+            .min()
+    }
+
+    fn track(&mut self, state: &CheatAnalyserState) {
+        let mut extended = HashSet::new();
+
+        for (tracker_id, tracker) in &state.player_states {
+            let (pitch, yaw, _) = match tracker.viewangles {
+                Some(angles) => angles,
+                None => continue,
+            };
+            let (x, y, z) = match tracker.position {
+                Some(position) => position,
+                None => continue,
+            };
+            let eye = (x, y, z + EYE_HEIGHT);
+            let aim = aim_direction(pitch, yaw);
+            let mut eligible = false;
+
+            for (target_id, target) in &state.player_states {
+                if target_id == tracker_id || !Self::is_enemy(tracker, target) || target.viewangles.is_none() {
+                    continue;
+                }
+                let (tx, ty, tz) = match target.position {
+                    Some(position) => position,
+                    None => continue,
+                };
+                let to_target = sub((tx, ty, tz + HULL_HEIGHT / 2.0), eye);
+                let distance = length(to_target);
+                if distance < 1.0 || !self.is_hidden(state, eye, to_target, *target_id) {
+                    continue;
+                }
+                eligible = true;
+
+                let direction = (to_target.0 / distance, to_target.1 / distance, to_target.2 / distance);
+                let off_target = dot(direction, aim).clamp(-1.0, 1.0).acos().to_degrees();
+                // Close enemies cover more of the screen than the fixed angle
+                let tolerance = ON_TARGET_ANGLE.max((HULL_RADIUS / distance).atan().to_degrees()) + state.yaw_precision(*tracker_id);
+                if off_target > tolerance {
+                    continue;
+                }
+
+                let key = (*tracker_id, *target_id);
+                match self.open_segments.entry(key) {
+                    Entry::Occupied(mut entry) => {
+                        let segment = entry.get_mut();
+                        segment.last = state.tick;
+                        segment.distance_sum += distance;
+                        let moved = dot(segment.start_direction, direction).clamp(-1.0, 1.0).acos().to_degrees();
+                        segment.bearing_change = segment.bearing_change.max(moved);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(TrackSegment {
+                            start: state.tick,
+                            last: state.tick,
+                            start_direction: direction,
+                            bearing_change: 0.0,
+                            distance_sum: distance,
+                        });
+                    }
+                }
+                extended.insert(key);
+            }
+
+            if eligible {
+                self.stats.entry(*tracker_id).or_default().eligible_ticks += 1;
+            }
+        }
+
+        // Segments that weren't continued this tick are over
+        let finished: Vec<(u64, u64)> = self.open_segments.keys()
+            .filter(|key| !extended.contains(key))
+            .copied()
+            .collect();
+        for key in finished {
+            if let Some(segment) = self.open_segments.remove(&key) {
+                self.close_segment(key.0, key.1, segment);
+            }
+        }
+    }
+}
+
+impl CheatAlgorithm for WallhackAnalyzer {
+    fn algorithm_name(&self) -> &str {
+        "wallhack_analyzer"
+    }
+
+    fn handled_messages(&self) -> Result<Vec<MessageType>, bool> {
+        Ok(vec![MessageType::GameEvent])
+    }
+
+    fn on_tick(&mut self, state: &CheatAnalyserState, _parser_state: &ParserState) -> Result<Vec<Detection>, Error> {
+        self.uses_map = state.map.is_some();
+
+        for (player_id, player_state) in &state.player_states {
+            if !player_state.name.is_empty() {
+                self.player_names.insert(*player_id, player_state.name.clone());
+            }
+        }
+
+        // Gunfire and pain sounds give a player's position away
+        for hit in &state.hit_events {
+            self.last_cue.insert(hit.attacker, hit.tick);
+            self.last_cue.insert(hit.victim, hit.tick);
+            self.hits.entry((hit.attacker, hit.victim)).or_default().push(hit.tick);
+        }
+
+        self.track(state);
+
+        // Ratios are judged against the lobby at the end of the demo
+        Ok(vec![])
+    }
+
+    fn on_message(&mut self, _message: &Message, _state: &CheatAnalyserState, _parser_state: &ParserState, _tick: DemoTick) -> Result<Vec<Detection>, Error> {
+        Ok(vec![])
+    }
+
+    fn finish(&mut self) -> Result<Vec<Detection>, Error> {
+        let open: Vec<((u64, u64), TrackSegment)> = self.open_segments.drain().collect();
+        for ((tracker, target), segment) in open {
+            self.close_segment(tracker, target, segment);
+        }
+
+        // (player, ratio, tracked ticks, counted segments, pre-aimed segments)
+        let mut ratios = Vec::new();
+        for (player_id, stats) in &self.stats {
+            if stats.eligible_ticks < MIN_ELIGIBLE_TICKS {
+                continue;
+            }
+            let mut counted = Vec::new();
+            let mut preaimed = 0;
+            for segment in &stats.segments {
+                match self.engaged_at(*player_id, segment) {
+                    // Never shot at, the target stayed hidden the whole time
+                    None => counted.push(segment),
+                    Some(hit_tick) if hit_tick.saturating_sub(segment.start) >= PREAIM_TICKS => {
+                        preaimed += 1;
+                        counted.push(segment);
+                    }
+                    Some(_) => {}
+                }
+            }
+            let tracked_ticks: u32 = counted.iter().map(|segment| segment.end - segment.start + 1).sum();
+            let ratio = tracked_ticks as f32 / stats.eligible_ticks as f32;
+            ratios.push((*player_id, ratio, tracked_ticks, counted, preaimed));
+        }
+
+        let baseline = (ratios.len() >= MIN_LOBBY_PLAYERS)
+            .then(|| Baseline::from_values(ratios.iter().map(|(_, ratio, ..)| *ratio).collect()));
+
+        let mut detections = Vec::new();
+        for (player_id, ratio, tracked_ticks, counted, preaimed) in &ratios {
+            if counted.len() < MIN_SEGMENTS || *ratio < MIN_TRACKING_RATIO {
+                continue;
+            }
+            // With a lobby to compare against only outliers are reported
+            let z_score = baseline.as_ref().map(|baseline| baseline.z_score(*ratio));
+            if z_score.map_or(false, |z| z < Z_SCORE_THRESHOLD) {
+                continue;
+            }
+
+            let longest = match counted.iter().max_by_key(|segment| segment.end - segment.start) {
+                Some(segment) => segment,
+                None => continue,
+            };
+            detections.push(Detection {
+                tick: longest.start,
+                algorithm: self.algorithm_name().to_string(),
+                player: *player_id,
+                data: json!({
+                    "type": "hidden_target_tracking",
+                    "player_name": self.player_names.get(player_id).cloned().unwrap_or_default(),
+                    "tracking_ratio": ratio,
+                    "lobby_median": baseline.as_ref().map(|baseline| baseline.median),
+                    "z": z_score,
+                    "tracked_ticks": tracked_ticks,
+                    "eligible_ticks": self.stats[player_id].eligible_ticks,
+                    "segments": counted.len(),
+                    "preaimed_segments": preaimed,
+                    "line_of_sight": if self.uses_map { "map" } else { "heuristic" },
+                    "longest_segment": {
+                        "target": longest.target,
+                        "target_name": self.player_names.get(&longest.target).cloned().unwrap_or_default(),
+                        "start_tick": longest.start,
+                        "length": longest.end - longest.start + 1,
+                        "bearing_change": longest.bearing_change,
+                        "mean_distance": longest.mean_distance,
+                    },
+                }),
+                timestamp: None,
+            });
+        }
+
+        self.stats.clear();
+        self.hits.clear();
+        self.last_cue.clear();
+        Ok(detections)
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use eframe::egui;
+use tf_demo_parser::demo::{
+    data::DemoTick,
+    message::{packetentities::EntityId, Message, MessageType},
+    parser::{
+        analyser::{Analyser, MatchState, Team},
+        DemoParser, MessageHandler, ParserState,
+    },
+    packet::stringtable::StringTableEntry,
+    Demo,
+};
+
+/// Which side of the recorder a player was on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Relation {
+    Recorder,
+    Teammate,
+    Enemy,
+    Unknown, // STV demos, spectators or players that never picked a team
+}
+
+impl Relation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Relation::Recorder => "Recorder",
+            Relation::Teammate => "Teammate",
+            Relation::Enemy => "Enemy",
+            Relation::Unknown => "?",
+        }
+    }
+
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            Relation::Recorder => egui::Color32::from_rgb(255, 170, 60),
+            Relation::Teammate => egui::Color32::from_rgb(90, 200, 110),
+            Relation::Enemy => egui::Color32::from_rgb(235, 80, 80),
+            Relation::Unknown => egui::Color32::GRAY,
+        }
+    }
+}
+
+/// What a demo says about its match beyond the names in the dump
+#[derive(Clone, Debug)]
+pub struct DemoInfo {
+    pub map: String,
+    pub server: String,
+    pub recorder: String, // "SourceTV" for STV demos
+    pub duration: f32,    // seconds
+    recorder_steamid: Option<String>,
+    teams: HashMap<String, Team>, // Steam32 -> team at the end of the demo
+}
+
+impl DemoInfo {
+    /// `steamid` is Steam32
+    pub fn relation(&self, steamid: &str) -> Relation {
+        if self.recorder_steamid.as_deref() == Some(steamid) {
+            return Relation::Recorder;
+        }
+        let same_team = self.recorder_steamid.as_deref()
+            .and_then(|recorder| self.same_team(recorder, steamid));
+        match same_team {
+            Some(true) => Relation::Teammate,
+            Some(false) => Relation::Enemy,
+            None => Relation::Unknown,
+        }
+    }
+
+    /// Whether two Steam32 ids ended the demo on the same team, `None` unless both played
+    pub fn same_team(&self, a: &str, b: &str) -> Option<bool> {
+        match (self.teams.get(a)?, self.teams.get(b)?) {
+            (a @ (Team::Red | Team::Blue), b @ (Team::Red | Team::Blue)) => Some(a == b),
+            _ => None,
+        }
+    }
+}
+
+/// The match analyser plus the recording client's slot from ServerInfo, the header
+/// only has the recorder's name and names aren't unique
+#[derive(Default)]
+struct InfoAnalyser {
+    analyser: Analyser,
+    recorder_entity: Option<EntityId>, // None for STV demos
+}
+
+impl MessageHandler for InfoAnalyser {
+    type Output = (MatchState, Option<EntityId>);
+
+    fn does_handle(message_type: MessageType) -> bool {
+        Analyser::does_handle(message_type)
+    }
+
+    fn handle_message(&mut self, message: &Message, tick: DemoTick, parser_state: &ParserState) {
+        if let Message::ServerInfo(info) = message {
+            // Player entities start at 1, slots at 0
+            self.recorder_entity = (!info.stv).then(|| EntityId::from(info.player_slot as u32 + 1));
+        }
+        self.analyser.handle_message(message, tick, parser_state);
+    }
+
+    fn handle_string_entry(&mut self, table: &str, index: usize, entry: &StringTableEntry, parser_state: &ParserState) {
+        self.analyser.handle_string_entry(table, index, entry, parser_state);
+    }
+
+    fn into_output(self, parser_state: &ParserState) -> Self::Output {
+        (self.analyser.into_output(parser_state), self.recorder_entity)
+    }
+}
+
+/// Parse a whole demo for its header and the final team of every player
+pub fn read_demo_info(path: &Path) -> Result<DemoInfo, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read demo file: {}", e))?;
+    let demo = Demo::new(&bytes);
+    let parser = DemoParser::new_with_analyser(demo.get_stream(), InfoAnalyser::default());
+    let (header, (state, recorder_entity)) = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| parser.parse())) {
+        Ok(result) => result.map_err(|e| e.to_string())?,
+        Err(_) => return Err("Demo parser panicked".to_string()),
+    };
+
+    let recorder_steamid = recorder_entity.and_then(|entity| {
+        state.users.values()
+            .find(|user| user.entity_id == entity)
+            .map(|user| user.steam_id.clone())
+    });
+    let teams = state.users.values()
+        .map(|user| (user.steam_id.clone(), user.team))
+        .collect();
+
+    Ok(DemoInfo {
+        map: header.map,
+        server: header.server,
+        recorder: header.nick,
+        duration: header.duration,
+        recorder_steamid,
+        teams,
+    })
+}
+
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+impl DemoInfo {
+    /// Demo info with only the recorder and teams filled in
+    pub(crate) fn with_teams(recorder_steamid: Option<&str>, teams: &[(&str, Team)]) -> Self {
+        Self {
+            map: String::new(),
+            server: String::new(),
+            recorder: String::new(),
+            duration: 0.0,
+            recorder_steamid: recorder_steamid.map(str::to_string),
+            teams: teams.iter().map(|(steamid, team)| (steamid.to_string(), *team)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> DemoInfo {
+        DemoInfo::with_teams(Some("[U:1:1]"), &[
+            ("[U:1:1]", Team::Red),
+            ("[U:1:2]", Team::Red),
+            ("[U:1:3]", Team::Blue),
+            ("[U:1:4]", Team::Spectator),
+            ("[U:1:5]", Team::Other),
+        ])
+    }
+
+    #[test]
+    fn relations_to_the_recorder() {
+        let info = info();
+        assert_eq!(info.relation("[U:1:1]"), Relation::Recorder);
+        assert_eq!(info.relation("[U:1:2]"), Relation::Teammate);
+        assert_eq!(info.relation("[U:1:3]"), Relation::Enemy);
+        assert_eq!(info.relation("[U:1:4]"), Relation::Unknown);
+        assert_eq!(info.relation("[U:1:5]"), Relation::Unknown);
+        assert_eq!(info.relation("[U:1:9]"), Relation::Unknown);
+    }
+
+    #[test]
+    fn stv_demos_have_no_sides() {
+        let info = DemoInfo::with_teams(None, &[("[U:1:2]", Team::Red), ("[U:1:3]", Team::Blue)]);
+        assert_eq!(info.relation("[U:1:2]"), Relation::Unknown);
+        assert_eq!(info.relation("[U:1:3]"), Relation::Unknown);
+        // Teams between players still count
+        assert_eq!(info.same_team("[U:1:2]", "[U:1:3]"), Some(false));
+    }
+
+    #[test]
+    fn same_team_needs_both_playing() {
+        let info = info();
+        assert_eq!(info.same_team("[U:1:1]", "[U:1:2]"), Some(true));
+        assert_eq!(info.same_team("[U:1:3]", "[U:1:1]"), Some(false));
+        assert_eq!(info.same_team("[U:1:1]", "[U:1:4]"), None);
+        assert_eq!(info.same_team("[U:1:4]", "[U:1:4]"), None);
+        assert_eq!(info.same_team("[U:1:1]", "[U:1:9]"), None);
+    }
+}
@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use eframe::egui;
+
+const ROW_HEIGHT: f32 = 20.0;
+const LABEL_WIDTH: f32 = 140.0;
+const MIN_BAR_WIDTH: f32 = 4.0;
+const BAR_COLOR: egui::Color32 = egui::Color32::from_rgb(74, 163, 255);
+const CURRENT_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 170, 60);
+
+/// One name a player used, with when and where it was seen
+#[derive(Clone, Debug)]
+pub struct NameUse {
+    pub name: String,
+    pub first_seen: u64, // unix seconds
+    pub last_seen: u64,
+    pub demos: Vec<(PathBuf, u64)>, // with when each was recorded
+    last_record: usize, // how many records came before the latest one of this name
+}
+
+/// Every name of a SteamID across the dumps, most recently used first
+#[derive(Clone, Debug, Default)]
+pub struct NameHistory {
+    pub names: Vec<NameUse>,
+    records: usize,
+}
+
+impl NameHistory {
+    /// Names seen at the same time are told apart by the order they're recorded in,
+    /// the later one is the newer name
+    pub fn record(&mut self, name: &str, demo: &Path, seen: u64) {
+        let record = self.records;
+        self.records += 1;
+        match self.names.iter_mut().find(|used| used.name == name) {
+            Some(used) => {
+                used.first_seen = used.first_seen.min(seen);
+                used.last_seen = used.last_seen.max(seen);
+                used.last_record = record;
+                if !used.demos.iter().any(|(known, _)| known == demo) {
+                    used.demos.push((demo.to_path_buf(), seen));
+                }
+            }
+            None => self.names.push(NameUse {
+                name: name.to_string(),
+                first_seen: seen,
+                last_seen: seen,
+                demos: vec![(demo.to_path_buf(), seen)],
+                last_record: record,
+            }),
+        }
+    }
+
+    /// Order names and their demos once everything is recorded. Of names last seen in the
+    /// same demo the one that showed up most recently is the newer one
+    pub fn finish(&mut self) {
+        self.names.sort_by(|a, b| {
+            b.last_seen.cmp(&a.last_seen)
+                .then_with(|| b.first_seen.cmp(&a.first_seen))
+                .then_with(|| b.last_record.cmp(&a.last_record))
+        });
+        for used in &mut self.names {
+            used.demos.sort();
+        }
+    }
+
+    /// Name from the most recent demo
+    pub fn current(&self) -> Option<&str> {
+        self.names.first().map(|used| used.name.as_str())
+    }
+
+    /// Names, most recently used first
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|used| used.name.as_str())
+    }
+}
+
+/// When a demo was recorded: the date in TF2's auto-record file names
+/// (2024-01-31_21-04-12.dem), otherwise the file's modified time
+pub fn demo_time(demo: &Path) -> u64 {
+    let stem = demo.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    if let Some(date) = stem.get(..19) {
+        if let Ok(date) = chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d_%H-%M-%S") {
+            if let Some(local) = date.and_local_timezone(chrono::Local).earliest() {
+                return local.timestamp().max(0) as u64;
+            }
+        }
+    }
+    std::fs::metadata(demo)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time: SystemTime| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+pub fn format_date(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "unknown".to_string();
+    }
+    let time: chrono::DateTime<chrono::Local> = (UNIX_EPOCH + Duration::from_secs(timestamp)).into();
+    time.format("%Y-%m-%d").to_string()
+}
+
+/// Bars from first to last use of each name on a shared date axis
+pub fn show_timeline(ui: &mut egui::Ui, history: &NameHistory) {
+    let dated: Vec<&NameUse> = history.names.iter().filter(|used| used.first_seen > 0).collect();
+    let (start, end) = match (dated.iter().map(|used| used.first_seen).min(), dated.iter().map(|used| used.last_seen).max()) {
+        (Some(start), Some(end)) => (start, end.max(start + 1)),
+        _ => {
+            ui.label("No dates known for this player's demos");
+            return;
+        }
+    };
+
+    let height = ROW_HEIGHT * (dated.len() + 1) as f32;
+    let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), height), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let plot_left = rect.left() + LABEL_WIDTH;
+    let plot_width = (rect.right() - plot_left - 8.0).max(1.0);
+    let x = |time: u64| plot_left + (time - start) as f32 / (end - start) as f32 * plot_width;
+    let font = egui::FontId::proportional(11.0);
+    let text_color = ui.visuals().text_color();
+    let weak_color = ui.visuals().weak_text_color();
+
+    let pointer = response.hover_pos();
+    let mut hovered = None;
+    for (row, used) in dated.iter().enumerate() {
+        let top = rect.top() + row as f32 * ROW_HEIGHT;
+        painter.text(egui::pos2(rect.left() + 4.0, top + ROW_HEIGHT / 2.0), egui::Align2::LEFT_CENTER, &used.name, font.clone(), text_color);
+
+        let left = x(used.first_seen);
+        let right = x(used.last_seen).max(left + MIN_BAR_WIDTH);
+        let bar = egui::Rect::from_min_max(egui::pos2(left, top + 4.0), egui::pos2(right, top + ROW_HEIGHT - 4.0));
+        let color = if Some(used.name.as_str()) == history.current() { CURRENT_COLOR } else { BAR_COLOR };
+        painter.rect_filled(bar, 2.0, color);
+        if pointer.map_or(false, |pointer| pointer.y >= top && pointer.y < top + ROW_HEIGHT) {
+            hovered = Some(*used);
+        }
+    }
+
+    let axis = rect.bottom() - ROW_HEIGHT / 2.0;
+    painter.text(egui::pos2(plot_left, axis), egui::Align2::LEFT_CENTER, format_date(start), font.clone(), weak_color);
+    painter.text(egui::pos2(plot_left + plot_width, axis), egui::Align2::RIGHT_CENTER, format_date(end), font, weak_color);
+
+    if let Some(used) = hovered {
+        response.on_hover_text(format!(
+            "{}\n{} - {}\n{} demos",
+            used.name,
+            format_date(used.first_seen),
+            format_date(used.last_seen),
+            used.demos.len()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(history: &NameHistory) -> Vec<&str> {
+        history.names().collect()
+    }
+
+    #[test]
+    fn most_recent_name_first() {
+        let mut history = NameHistory::default();
+        history.record("new", Path::new("c.dem"), 300);
+        history.record("old", Path::new("a.dem"), 100);
+        history.record("middle", Path::new("b.dem"), 200);
+        history.record("old", Path::new("a2.dem"), 150);
+        history.finish();
+
+        assert_eq!(names(&history), ["new", "middle", "old"]);
+        assert_eq!(history.current(), Some("new"));
+        let old = &history.names[2];
+        assert_eq!((old.first_seen, old.last_seen), (100, 150));
+        assert_eq!(old.demos, [(PathBuf::from("a.dem"), 100), (PathBuf::from("a2.dem"), 150)]);
+    }
+
+    #[test]
+    fn same_demo_twice_is_one_demo() {
+        let mut history = NameHistory::default();
+        history.record("name", Path::new("a.dem"), 100);
+        history.record("name", Path::new("a.dem"), 100);
+        history.finish();
+        assert_eq!(history.names[0].demos.len(), 1);
+    }
+
+    #[test]
+    fn two_names_in_one_demo() {
+        // Renamed in the last demo, the name that's only been seen there is the new one
+        let mut history = NameHistory::default();
+        history.record("zed", Path::new("b.dem"), 200);
+        history.record("alpha", Path::new("a.dem"), 100);
+        history.record("alpha", Path::new("b.dem"), 200);
+        history.finish();
+        assert_eq!(history.current(), Some("zed"));
+
+        // Both new in that demo, the later record wins rather than the alphabetical first
+        let mut history = NameHistory::default();
+        history.record("alpha", Path::new("a.dem"), 100);
+        history.record("zed", Path::new("a.dem"), 100);
+        history.finish();
+        assert_eq!(names(&history), ["zed", "alpha"]);
+    }
+
+    #[test]
+    fn empty_history() {
+        let mut history = NameHistory::default();
+        history.finish();
+        assert_eq!(history.current(), None);
+    }
+}
@@ -2,10 +2,10 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Instant;
@@ -31,17 +31,28 @@ use winreg::RegKey;
 use image::io::Reader as ImageReader;
 use std::io::Cursor;
 use std::time::SystemTime;
-use tokio;
 use rand::Rng;
-use bitbuffer::BitRead;
+use tokio;
 use chrono;
 use csv;
 
 // Add at the top with other imports
 mod cheater_detection;
+mod vdm;
+mod report;
+mod timeline;
+mod radar;
+mod playerlist;
+mod history;
+mod encounters;
+mod relations;
+mod alts;
 use cheater_detection::{
     base::{CheatAnalyser, CheatDemoHandler},
-    viewangles::ViewAnglesAnalyzer,
+    viewangles::{ViewAngleRecording, ViewAnglesAnalyzer},
+    all_algorithms,
+    CheatAlgorithm,
+    CheatAnalyserState,
     Detection
 };
 
@@ -133,6 +144,7 @@ impl ViewAnglesToCSV {
                                 "viewangle": yaw,
                                 "pitchangle": pitch,
                             }),
+                            timestamp: None,
                         });
                     }
                 }
@@ -161,7 +173,10 @@ struct AppSettings {
     demo_folder: PathBuf,
     output_path: PathBuf,
     all_output_paths: Vec<PathBuf>,
-    player_list_url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    player_list_url: String, // single list of older versions, moved into player_lists on load
+    #[serde(default)]
+    player_lists: Vec<playerlist::ListSource>,
     dark_mode: bool,
 }
 
@@ -173,7 +188,8 @@ impl Default for AppSettings {
             demo_folder: current_dir.clone(),
             output_path: current_dir.join("demo_dump.json"),
             all_output_paths: vec![current_dir.join("demo_dump.json")],
-            player_list_url: "https://github.com/AveraFox/Tom/blob/main/playerlist.vorobey-hackerpolice.json".to_string(),
+            player_list_url: String::new(),
+            player_lists: vec![playerlist::ListSource::new("Hacker Police", playerlist::DEFAULT_LIST_URL)],
             dark_mode: true,
         }
     }
@@ -184,7 +200,13 @@ impl AppSettings {
         let settings_path = PathBuf::from("dd_settings.cfg");
         if settings_path.exists() {
             if let Ok(contents) = fs::read_to_string(&settings_path) {
-                if let Ok(settings) = serde_json::from_str(&contents) {
+                if let Ok(mut settings) = serde_json::from_str::<AppSettings>(&contents) {
+                    if !settings.player_list_url.is_empty() {
+                        let url = std::mem::take(&mut settings.player_list_url);
+                        if !settings.player_lists.iter().any(|source| source.url == url) {
+                            settings.player_lists.push(playerlist::ListSource::new("Player list", &url));
+                        }
+                    }
                     return settings;
                 }
             }
@@ -200,17 +222,6 @@ impl AppSettings {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct PlayerListFile {
-    players: Vec<PlayerListEntry>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct PlayerListEntry {
-    steamid: String,
-    proof: Vec<String>,
-    attributes: Vec<String>,
-}
 
 #[derive(Clone)]
 struct ParseResult {
@@ -219,6 +230,42 @@ struct ParseResult {
 }
 
 type PlayerMap = HashMap<String, String>;
+type CheckResult = Result<(Vec<Detection>, Option<PathBuf>, ViewAngleRecording), String>;
+
+// Cheat analysis keeps a full viewangle recording in memory per demo, so fewer
+// demos are checked at once than are parsed by start_processing
+const MAX_CHECK_WORKERS: usize = 4;
+// Recordings kept for the timeline and radar after their check, oldest are dropped first
+const MAX_KEPT_RECORDINGS: usize = 8;
+// Detections of a player added as proof when marking them from the Demo Checker
+const MAX_MARK_PROOF: usize = 5;
+// Most recent demos of a player referenced in an exported list
+const MAX_EXPORT_DEMOS: usize = 10;
+// Rows of the "Played with / against" table in the player detail window
+const MAX_CO_PLAYERS: usize = 100;
+
+/// Progress of a demo queued for cheat analysis, sent by the check workers
+enum CheckUpdate {
+    Started(PathBuf),
+    Progress(PathBuf, f32), // share of the demo's ticks analysed
+    Log(String),
+    Finished(PathBuf, CheckResult),
+}
+
+#[derive(Clone, PartialEq)]
+enum CheckStatus {
+    Queued,
+    Running(f32), // progress 0..1
+    Done(usize), // number of detections
+    Failed(String),
+}
+
+/// Demos waiting for a background worker, workers exit once it's empty
+#[derive(Default)]
+struct CheckQueue {
+    jobs: VecDeque<PathBuf>,
+    workers: usize,
+}
 type Output = HashMap<PathBuf, PlayerMap>;
 
 #[derive(Clone, Debug)]
@@ -226,8 +273,7 @@ struct PlayerStats {
     username: String,
     steamid: String,
     demo_count: usize,
-    is_cheater: bool,
-    proof: Option<String>,
+    marks: Vec<playerlist::SourceMark>, // what each enabled list says, most trusted first
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -266,15 +312,14 @@ struct AppState {
     save_counter: usize,
     copy_status: Option<String>,
     all_output_paths: Vec<PathBuf>,
-    player_list_url: String,
-    player_list: Vec<PlayerListEntry>,
+    player_lists: playerlist::PlayerLists,
     player_stats: Vec<PlayerStats>,
     exclude_string: String,
     bad_actor_count: usize,
     player_list_loaded: bool,
     players_page: usize,
     players_per_page: usize,
-    steamid_to_usernames: HashMap<String, HashSet<String>>,
+    name_histories: Arc<HashMap<String, history::NameHistory>>, // Names of each SteamID across the dumps
     demo_browser_search: String,
     demo_browser_results: Vec<(PathBuf, String, Option<Vec<(String, String, Vec<String>)>>)>,
     demo_list: Vec<(PathBuf, String)>,
@@ -294,11 +339,39 @@ struct AppState {
     detection_method: Option<DetectionMethod>, // Currently selected detection method
     oob_threshold: Option<f32>, // Threshold for out-of-bounds pitch detection
     last_oob_summary_path: Option<PathBuf>,
+    demo_detections: HashMap<PathBuf, Vec<Detection>>, // Detections from the last Check Demo per demo
+    demo_viewangles_files: HashMap<PathBuf, PathBuf>, // Viewangles CSV from the last Check Demo per demo
+    demo_recordings: HashMap<PathBuf, Arc<ViewAngleRecording>>, // Viewangles of the latest checks, for the plots
+    recording_order: VecDeque<PathBuf>, // Demos in demo_recordings, oldest first
+    viewangle_timeline: Option<timeline::ViewAngleTimeline>, // Plot of the selected checked demo
+    radar_playback: Option<radar::RadarPlayback>, // Top-down replay of the selected checked demo
+    demo_check_status: HashMap<PathBuf, CheckStatus>, // Queued and finished cheat analyses
+    checks_total: usize, // Demos queued for cheat analysis since the queue was last empty
+    checks_done: usize,
+    mark_editor: Option<playerlist::MarkEditor>, // Open editor of a local player list entry
+    selected_players: HashSet<String>, // Steam32 ids ticked in the Players tab for export
+    export_attribute: playerlist::PlayerAttribute,
+    export_format: playerlist::ListFormat,
+    player_detail: Option<String>, // Steam32 id of the player shown in the detail window
+    demo_infos: HashMap<PathBuf, Result<encounters::DemoInfo, String>>, // Map, server and teams of demos shown in the detail window
+    demo_infos_loading: HashSet<PathBuf>,
+    demo_players: Arc<HashMap<PathBuf, Vec<String>>>, // Steam32 ids in each demo across the dumps
+    relations_cheaters_only: bool,
+    relations_min_demos: usize,
+    alt_reviews: alts::AltReviews, // Confirmed and rejected alt groups, saved to ALT_REVIEWS_FILE
+    alt_clusters: Vec<alts::AltCluster>, // Result of the last alt search
+    alt_search_running: bool,
+    show_alts: bool,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         let settings = AppSettings::load();
+        let mut log_messages = vec!["Welcome to TF2 Demo Parser!".to_string()];
+        let alt_reviews = alts::AltReviews::load().unwrap_or_else(|e| {
+            log_messages.push(e);
+            alts::AltReviews::default()
+        });
         Self {
             total_demos: 0,
             processed_demos: 0,
@@ -306,21 +379,20 @@ impl Default for AppState {
             is_processing: false,
             start_time: None,
             current_demo: None,
-            log_messages: vec!["Welcome to TF2 Demo Parser!".to_string()],
+            log_messages,
             demo_folder: settings.demo_folder,
             output_path: settings.output_path,
             save_counter: 0,
             copy_status: None,
             all_output_paths: settings.all_output_paths,
-            player_list_url: settings.player_list_url,
-            player_list: Vec::new(),
+            player_lists: playerlist::PlayerLists::new(settings.player_lists),
             player_stats: Vec::new(),
             exclude_string: String::new(),
             bad_actor_count: 0,
             player_list_loaded: false,
             players_page: 0,
             players_per_page: 50,
-            steamid_to_usernames: HashMap::new(),
+            name_histories: Arc::new(HashMap::new()),
             demo_browser_search: String::new(),
             demo_browser_results: Vec::new(),
             demo_list: Vec::new(),
@@ -340,8 +412,56 @@ impl Default for AppState {
             detection_method: None,
             oob_threshold: None,
             last_oob_summary_path: None,
+            demo_detections: HashMap::new(),
+            demo_viewangles_files: HashMap::new(),
+            demo_recordings: HashMap::new(),
+            recording_order: VecDeque::new(),
+            viewangle_timeline: None,
+            radar_playback: None,
+            demo_check_status: HashMap::new(),
+            checks_total: 0,
+            checks_done: 0,
+            mark_editor: None,
+            selected_players: HashSet::new(),
+            export_attribute: playerlist::PlayerAttribute::Cheater,
+            export_format: playerlist::ListFormat::Tf2bd,
+            player_detail: None,
+            demo_infos: HashMap::new(),
+            demo_infos_loading: HashSet::new(),
+            demo_players: Arc::new(HashMap::new()),
+            relations_cheaters_only: false,
+            relations_min_demos: 2,
+            alt_reviews,
+            alt_clusters: Vec::new(),
+            alt_search_running: false,
+            show_alts: false,
+        }
+    }
+}
+
+impl AppState {
+    /// Keep a checked demo's recording for the plots, dropping the oldest past the limit
+    fn keep_recording(&mut self, demo: PathBuf, recording: Arc<ViewAngleRecording>) {
+        self.recording_order.retain(|kept| kept != &demo);
+        self.recording_order.push_back(demo.clone());
+        self.demo_recordings.insert(demo, recording);
+        while self.recording_order.len() > MAX_KEPT_RECORDINGS {
+            if let Some(oldest) = self.recording_order.pop_front() {
+                self.demo_recordings.remove(&oldest);
+            }
         }
     }
+
+    /// Point the timeline and radar at a checked demo
+    fn show_demo_plots(&mut self, demo: &Path) {
+        let recording = match self.demo_recordings.get(demo) {
+            Some(recording) => recording.clone(),
+            None => return,
+        };
+        let detections = self.demo_detections.get(demo).cloned().unwrap_or_default();
+        self.viewangle_timeline = Some(timeline::ViewAngleTimeline::new(demo.to_path_buf(), recording.clone(), detections.clone()));
+        self.radar_playback = Some(radar::RadarPlayback::new(recording, detections));
+    }
 }
 
 struct DemoParserApp {
@@ -349,6 +469,11 @@ struct DemoParserApp {
     result_receiver: Option<Receiver<ParseResult>>,
     output: Arc<Mutex<Output>>,
     worker_handles: Vec<thread::JoinHandle<()>>,
+    check_queue: Arc<Mutex<CheckQueue>>,
+    info_queue: Arc<Mutex<CheckQueue>>, // demos parsed for the player detail window
+    info_queue_player: Option<String>,  // whose demos are queued
+    check_sender: Sender<CheckUpdate>,
+    check_receiver: Receiver<CheckUpdate>,
     scroll_to_bottom: bool,
     current_tab: usize,
     player_list_initialized: bool,
@@ -357,11 +482,17 @@ struct DemoParserApp {
 
 impl Default for DemoParserApp {
     fn default() -> Self {
+        let (check_sender, check_receiver) = channel();
         Self {
             state: Arc::new(Mutex::new(AppState::default())),
             result_receiver: None,
             output: Arc::new(Mutex::new(HashMap::new())),
             worker_handles: Vec::new(),
+            check_queue: Arc::new(Mutex::new(CheckQueue::default())),
+            info_queue: Arc::new(Mutex::new(CheckQueue::default())),
+            info_queue_player: None,
+            check_sender,
+            check_receiver,
             scroll_to_bottom: true,
             current_tab: 0,
             player_list_initialized: false,
@@ -444,6 +575,29 @@ impl eframe::App for DemoParserApp {
             }
         }
 
+        // Handle cheat analysis progress
+        let check_updates: Vec<CheckUpdate> = self.check_receiver.try_iter().collect();
+        for update in check_updates {
+            needs_repaint = true;
+            match update {
+                CheckUpdate::Started(demo) => {
+                    let mut state = self.state.lock().unwrap();
+                    state.demo_check_status.insert(demo, CheckStatus::Running(0.0));
+                }
+                CheckUpdate::Progress(demo, progress) => {
+                    let mut state = self.state.lock().unwrap();
+                    state.demo_check_status.insert(demo, CheckStatus::Running(progress));
+                }
+                CheckUpdate::Log(message) => {
+                    self.state.lock().unwrap().log_messages.push(message);
+                    self.scroll_to_bottom = true;
+                }
+                CheckUpdate::Finished(demo, result) => {
+                    self.handle_check_result(demo, result);
+                }
+            }
+        }
+
         // Limit framerate when idle
         if !needs_repaint && !self.state.lock().unwrap().is_processing {
             ctx.request_repaint_after(std::time::Duration::from_secs_f32(1.0 / 10.0)); // 10 FPS when idle
@@ -555,6 +709,40 @@ impl eframe::App for DemoParserApp {
                 _ => {}
             }
         });
+
+        self.render_alt_window(ctx);
+        self.render_player_detail(ctx);
+
+        // Editor of our own player list, opened from any tab
+        let editor = self.state.lock().unwrap().mark_editor.take();
+        if let Some(mut editor) = editor {
+            let mut open = true;
+            let action = editor.show(ctx, &mut open);
+            let mut state = self.state.lock().unwrap();
+            if open {
+                state.mark_editor = Some(editor);
+            }
+            if let Some(action) = action {
+                let result = match action {
+                    playerlist::MarkAction::Save(entry) => {
+                        let steamid = entry.steamid.clone();
+                        state.player_lists.mark_local(entry).map(|_| format!("Marked {} in the local list", steamid))
+                    }
+                    playerlist::MarkAction::Remove(steamid) => {
+                        state.player_lists.unmark_local(&steamid).map(|_| format!("Removed {} from the local list", steamid))
+                    }
+                };
+                match result {
+                    Ok(message) => {
+                        state.copy_status = Some(message.clone());
+                        state.log_messages.push(message);
+                    }
+                    Err(e) => state.log_messages.push(format!("Error saving local player list: {}", e)),
+                }
+                drop(state);
+                self.update_player_stats();
+            }
+        }
         
         // Only request immediate repaint if something changed
         if needs_repaint {
@@ -919,7 +1107,6 @@ impl DemoParserApp {
             let mut state = self.state.lock().unwrap();
             update_filtered_players(&mut state);
             (
-                state.player_list_url.clone(),
                 state.player_list_loaded,
                 state.filtered_players.clone(),
                 state.exclude_string.clone(),
@@ -929,7 +1116,7 @@ impl DemoParserApp {
                 state.player_stats.len(),
             )
         };
-        let (player_list_url, player_list_loaded, filtered_players, exclude_string, 
+        let (player_list_loaded, filtered_players, exclude_string, 
              bad_actor_count, copy_status, dark_mode, total_players) = state_data;
 
         // Calculate available height for the scroll area
@@ -953,56 +1140,34 @@ impl DemoParserApp {
         ui.separator();
             ui.add_space(vertical_spacing);
         
-        // Player list URL input
+        // Player list sources
         ui.horizontal(|ui| {
                 ui.add_space(horizontal_margin);
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    let button_area_width = 120.0;
-                    let mut button_height = ui.spacing().interact_size.y; // Initialize with default height
-
-                    // Button Part (far right)
-                    let _update_btn_response = ui.allocate_ui_with_layout(
-                        egui::vec2(button_area_width, button_height), 
-                        egui::Layout::right_to_left(egui::Align::Center), 
-                        |ui_btn_area| {
-                            let btn = ui_btn_area.button("🔄 Update List")
-                                .on_hover_text("Update player list from URL");
-                            button_height = btn.rect.height(); // Update height from actual button
-                            if btn.clicked() {
-                let mut state = self.state.lock().unwrap();
-                state.player_list_url = player_list_url.clone();
-                                state.player_list_loaded = false;
-                                drop(state);
-                                self.load_player_list();
-                            }
-                        }
-                    );
-                    
-                    // TextEdit part
-                    let text_edit_width = (ui.available_width() - label_width).max(50.0);
-                    let mut current_url = player_list_url.clone();
-                    let text_edit_response = ui.add_sized(
-                        [text_edit_width, button_height],
-                        egui::TextEdit::singleline(&mut current_url)
-                            .font(ui.style().text_styles.get(&egui::TextStyle::Body).unwrap().clone())
-                    ).on_hover_text("Enter the URL for the player list");
-
-                    if text_edit_response.changed() {
-                        // Optionally update state.player_list_url live or on Enter/lost_focus
+                ui.vertical(|ui| {
+                    let oldest_update = self.state.lock().unwrap().player_lists.oldest_update();
+                    let title = match oldest_update {
+                        Some(updated) => format!("Player lists (updated {})", playerlist::format_age(updated)),
+                        None => "Player lists (not loaded yet)".to_string(),
+                    };
+                    egui::CollapsingHeader::new(title)
+                        .id_source("player_lists")
+                        .show(ui, |ui| {
+                            self.render_player_list_sources(ui);
+                        });
+                    if ui.button("🔄 Update Lists")
+                        .on_hover_text("Download every enabled player list again")
+                        .clicked()
+                    {
+                        self.state.lock().unwrap().player_list_loaded = false;
+                        self.load_player_list();
+                    }
+                    if ui.button("🕵 Find Alts")
+                        .on_hover_text("Group accounts that look like the same person, for you to confirm or reject")
+                        .clicked()
+                    {
+                        self.state.lock().unwrap().show_alts = true;
+                        self.find_alts();
                     }
-                    if text_edit_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                let mut state = self.state.lock().unwrap();
-                        state.player_list_url = current_url.clone();
-                state.player_list_loaded = false;
-                drop(state);
-                self.load_player_list();
-            }
-                    
-                    // Label part (far left)
-                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                        ui.set_min_width(label_width);
-                        ui.label("Player list URL:");
-                    });
                 });
                 ui.add_space(horizontal_margin);
         });
@@ -1101,6 +1266,75 @@ impl DemoParserApp {
                 ui.add_space(horizontal_margin);
             });
             ui.add_space(vertical_spacing);
+
+            // Export the ticked players, or everyone matching the filter when none are ticked
+            ui.horizontal(|ui| {
+                ui.add_space(horizontal_margin);
+                let (selected_count, mut attribute, mut format) = {
+                    let state = self.state.lock().unwrap();
+                    (state.selected_players.len(), state.export_attribute, state.export_format)
+                };
+                ui.strong("Export:");
+                egui::ComboBox::from_id_source("export_attribute")
+                    .selected_text(attribute.label())
+                    .show_ui(ui, |ui| {
+                        for option in playerlist::PlayerAttribute::ALL {
+                            ui.selectable_value(&mut attribute, option, option.label());
+                        }
+                    });
+                egui::ComboBox::from_id_source("export_format")
+                    .selected_text(format.label())
+                    .show_ui(ui, |ui| {
+                        for option in playerlist::ListFormat::ALL {
+                            ui.selectable_value(&mut format, option, option.label());
+                        }
+                    });
+                {
+                    let mut state = self.state.lock().unwrap();
+                    state.export_attribute = attribute;
+                    state.export_format = format;
+                }
+
+                let label = if selected_count > 0 {
+                    format!("📤 Export {} selected", selected_count)
+                } else {
+                    format!("📤 Export {} shown", filtered_players.len())
+                };
+                if ui.button(label).clicked() {
+                    let players: Vec<PlayerStats> = if selected_count > 0 {
+                        let state = self.state.lock().unwrap();
+                        state.player_stats.iter()
+                            .filter(|player| state.selected_players.contains(&player.steamid))
+                            .cloned()
+                            .collect()
+                    } else {
+                        filtered_players.clone()
+                    };
+                    if let Some(path) = FileDialog::new()
+                        .add_filter("Player list", &["json"])
+                        .set_file_name("playerlist.json")
+                        .save_file()
+                    {
+                        let result = self.export_player_list(&players, &path);
+                        let mut state = self.state.lock().unwrap();
+                        match result {
+                            Ok(count) => {
+                                state.copy_status = Some(format!("Exported {} players to {}", count, path.to_string_lossy()));
+                                state.log_messages.push(format!("Exported {} players to {}", count, path.to_string_lossy()));
+                            }
+                            Err(e) => state.log_messages.push(format!("Error exporting player list: {}", e)),
+                        }
+                    }
+                }
+                if selected_count > 0 && ui.button("Clear selection").clicked() {
+                    self.state.lock().unwrap().selected_players.clear();
+                }
+                if ui.button("Select shown").clicked() {
+                    let mut state = self.state.lock().unwrap();
+                    state.selected_players.extend(filtered_players.iter().map(|player| player.steamid.clone()));
+                }
+            });
+            ui.add_space(vertical_spacing);
         });
 
         if filtered_players.is_empty() {
@@ -1172,7 +1406,21 @@ impl DemoParserApp {
                                 // Username column
                                 ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                                     ui.set_min_width(username_width);
-                                    ui.label(&player.username);
+                                    let mut selected = self.state.lock().unwrap().selected_players.contains(&player.steamid);
+                                    if ui.checkbox(&mut selected, "").on_hover_text("Select for export").changed() {
+                                        let mut state = self.state.lock().unwrap();
+                                        if selected {
+                                            state.selected_players.insert(player.steamid.clone());
+                                        } else {
+                                            state.selected_players.remove(&player.steamid);
+                                        }
+                                    }
+                                    if ui.add(egui::Label::new(&player.username).sense(egui::Sense::click()))
+                                        .on_hover_text("Show player details")
+                                        .clicked()
+                                    {
+                                        self.state.lock().unwrap().player_detail = Some(player.steamid.clone());
+                                    }
                                 });
                                 
                                 // SteamID column with copy button and cheater status
@@ -1209,21 +1457,22 @@ impl DemoParserApp {
                                         };
                                         ui.label(&display_id);
                                 
-                                        // Add cheater status button if player is marked
-                                if player.is_cheater {
+                                        // One badge per list that marks the player
+                                        for mark in &player.marks {
                                             ui.add_space(4.0);
-                                            if let Some(ref proof) = player.proof {
-                                                if ui.button("⚠ Cheater")
-                                                    .on_hover_text("Click to view evidence")
-                                                    .clicked() 
-                                                {
-                                            if let Err(e) = webbrowser::open(proof) {
-                                                let mut state = self.state.lock().unwrap();
+                                            if playerlist::mark_badge(ui, mark).clicked() {
+                                                if let Some(proof) = mark.proof.first() {
+                                                    if let Err(e) = webbrowser::open(proof) {
+                                                        let mut state = self.state.lock().unwrap();
                                                         state.log_messages.push(format!("Failed to open URL: {}", e));
                                                     }
                                                 }
                                             }
-                                }
+                                        }
+                                        ui.add_space(4.0);
+                                        if ui.small_button("🏷").on_hover_text("Mark in the local player list").clicked() {
+                                            self.open_mark_editor(&player.steamid, &player.username, Vec::new());
+                                        }
                             });
                         });
                                 
@@ -1259,6 +1508,96 @@ impl DemoParserApp {
             });
     }
     
+    /// Editable table of subscribed player lists
+    fn render_player_list_sources(&mut self, ui: &mut egui::Ui) {
+        let (mut sources, counts, errors) = {
+            let state = self.state.lock().unwrap();
+            let counts: Vec<(Option<usize>, Option<u64>)> = state.player_lists.sources.iter()
+                .map(|source| (state.player_lists.entry_count(&source.url), state.player_lists.last_updated(&source.url)))
+                .collect();
+            let errors: Vec<Option<String>> = state.player_lists.sources.iter()
+                .map(|source| state.player_lists.error(source))
+                .collect();
+            (state.player_lists.sources.clone(), counts, errors)
+        };
+        let mut edited = false;   // text changed, saved once editing is done
+        let mut committed = false;
+        let mut remove = None;
+
+        egui::Grid::new("player_list_sources")
+            .num_columns(8)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                ui.strong("On");
+                ui.strong("Name");
+                ui.strong("Colour");
+                ui.strong("Trust");
+                ui.strong("URL");
+                ui.strong("Players");
+                ui.strong("Updated");
+                ui.label("");
+                ui.end_row();
+
+                for (index, source) in sources.iter_mut().enumerate() {
+                    committed |= ui.checkbox(&mut source.enabled, "").changed();
+                    let name = ui.add(egui::TextEdit::singleline(&mut source.name).desired_width(120.0));
+                    committed |= ui.color_edit_button_srgb(&mut source.color).changed();
+                    egui::ComboBox::from_id_source(("list_trust", index))
+                        .selected_text(source.trust.label())
+                        .width(70.0)
+                        .show_ui(ui, |ui| {
+                            for trust in playerlist::TrustLevel::ALL {
+                                committed |= ui.selectable_value(&mut source.trust, trust, trust.label()).changed();
+                            }
+                        });
+                    let url = ui.add(egui::TextEdit::singleline(&mut source.url)
+                        .hint_text("https://... or file:///path/to/list.json")
+                        .desired_width(320.0));
+                    if url.changed() {
+                        source.resolve();
+                    }
+                    edited |= name.changed() || url.changed();
+                    committed |= name.lost_focus() || url.lost_focus();
+                    let (count, updated) = counts.get(index).copied().unwrap_or_default();
+                    ui.label(count.map_or("-".to_string(), |count| count.to_string()));
+                    match errors.get(index).cloned().flatten() {
+                        Some(error) => {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠ error").on_hover_text(error);
+                        }
+                        None => {
+                            ui.label(updated.map_or("never".to_string(), playerlist::format_age));
+                        }
+                    }
+                    if ui.button("🗑").on_hover_text("Remove this list").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        for (source, error) in sources.iter().zip(&errors) {
+            if let Some(error) = error {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{}: {}", source.name, error));
+            }
+        }
+
+        if ui.button("➕ Add list").clicked() {
+            sources.push(playerlist::ListSource::new("New list", ""));
+            committed = true;
+        }
+        if let Some(index) = remove {
+            sources.remove(index);
+            committed = true;
+        }
+
+        if edited || committed {
+            self.state.lock().unwrap().player_lists.sources = sources;
+        }
+        if committed {
+            self.save_player_lists();
+        }
+    }
+    
     fn render_demo_browser_tab(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         // Get all necessary state data at once with a single lock
         let state_data = {
@@ -1274,11 +1613,12 @@ impl DemoParserApp {
                 state.demo_analyses.clone(),
                 state.tf2_folder.clone(),
                 state.delete_replays_confirmation,
+                state.player_lists.merged(),
             )
         };
         let (demo_folder, demo_list, demo_browser_search, demo_browser_results, 
              copy_status, demo_sort_method, demo_sort_reversed, demo_analyses, 
-             tf2_folder, delete_confirmation) = state_data;
+             tf2_folder, delete_confirmation, marked_players) = state_data;
 
         ui.vertical_centered(|ui| {
             ui.heading("Demo Browser");
@@ -1631,7 +1971,19 @@ impl DemoParserApp {
                                                     // Username column
                                                     ui.scope(|ui| {
                                                         ui.set_width(name_width);
-                                                        ui.label(format!("• {}", username));
+                                                        ui.horizontal(|ui| {
+                                                            ui.label(format!("• {}", username));
+                                                            for mark in marked_players.get(steamid).map(|merged| merged.marks.as_slice()).unwrap_or(&[]) {
+                                                                if playerlist::mark_badge(ui, mark).clicked() {
+                                                                    if let Some(proof) = mark.proof.first() {
+                                                                        let _ = webbrowser::open(proof);
+                                                                    }
+                                                                }
+                                                            }
+                                                            if ui.small_button("🏷").on_hover_text("Mark in the local player list").clicked() {
+                                                                self.open_mark_editor(steamid, username, vec![path.to_string_lossy().to_string()]);
+                                                            }
+                                                        });
                                                     });
 
                                                     // SteamID column
@@ -1757,6 +2109,44 @@ impl DemoParserApp {
             ui.separator();
         }
         
+        // Viewangle plot of the last checked demo
+        let mut copied_command = None;
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(timeline) = state.viewangle_timeline.as_mut() {
+                let title = format!("📈 Viewangle Timeline - {}", timeline.demo_path.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default());
+                egui::CollapsingHeader::new(title)
+                    .id_source("viewangle_timeline")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        copied_command = timeline.show(ui);
+                    });
+            }
+            if let Some(radar) = state.radar_playback.as_mut() {
+                egui::CollapsingHeader::new("🗺 Radar Playback")
+                    .id_source("radar_playback")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        radar.show(ui);
+                    });
+            }
+            if state.viewangle_timeline.is_some() || state.radar_playback.is_some() {
+                ui.separator();
+            }
+        }
+        if let Some(command) = copied_command {
+            if let Ok(mut clipboard) = Clipboard::new() {
+                let mut state = self.state.lock().unwrap();
+                if clipboard.set_text(&command).is_ok() {
+                    state.copy_status = Some(format!("Copied to clipboard: {}", command));
+                } else {
+                    state.copy_status = Some("Failed to copy to clipboard".to_string());
+                }
+            }
+        }
+        
         // Folder selection
         ui.vertical_centered(|ui| {
             ui.horizontal(|ui| {
@@ -1904,6 +2294,36 @@ impl DemoParserApp {
                 ui.label(format!("Found {} demos", items.len()));
             });
 
+            // Cheat analysis queue
+            let (checks_total, checks_done, running_progress) = {
+                let state = self.state.lock().unwrap();
+                let running_progress: f32 = state.demo_check_status.values()
+                    .map(|status| match status {
+                        CheckStatus::Running(progress) => *progress,
+                        _ => 0.0,
+                    })
+                    .sum();
+                (state.checks_total, state.checks_done, running_progress)
+            };
+            ui.horizontal(|ui| {
+                if ui.button("Check All")
+                    .on_hover_text("Queue every listed demo for cheat analysis")
+                    .clicked()
+                {
+                    let demos = items.iter().map(|(path, _, _)| path.to_path_buf()).collect();
+                    self.queue_demo_checks(demos);
+                }
+                if checks_total > 0 {
+                    ui.add(egui::ProgressBar::new((checks_done as f32 + running_progress) / checks_total as f32)
+                        .desired_width(200.0)
+                        .text(format!("{}/{} checked", checks_done, checks_total)));
+                    if ui.button("Cancel queue").clicked() {
+                        self.cancel_demo_checks();
+                    }
+                    ctx.request_repaint();
+                }
+            });
+
             // Constants for virtual scrolling
             const ITEM_HEIGHT: f32 = 100.0;
             const BUFFER_ITEMS: usize = 2;
@@ -1957,69 +2377,93 @@ impl DemoParserApp {
                                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                         ui.spacing_mut().item_spacing.x = 8.0;
                                         
-                                        if ui.button("Check Demo").clicked() {
-                                            let path_clone = path.clone();
+                                        let check_status = self.state.lock().unwrap().demo_check_status.get(*path).cloned();
+                                        let checking = matches!(check_status, Some(CheckStatus::Queued | CheckStatus::Running(_)));
+                                        if ui.add_enabled(!checking, egui::Button::new("Check Demo")).clicked() {
+                                            self.queue_demo_checks(vec![path.to_path_buf()]);
+                                        }
+                                        match &check_status {
+                                            Some(CheckStatus::Queued) => {
+                                                ui.label("queued");
+                                            }
+                                            Some(CheckStatus::Running(progress)) => {
+                                                ui.add(egui::ProgressBar::new(*progress).desired_width(80.0).show_percentage());
+                                            }
+                                            Some(CheckStatus::Done(count)) => {
+                                                ui.label(format!("{} detections", count));
+                                            }
+                                            Some(CheckStatus::Failed(error)) => {
+                                                ui.label("failed").on_hover_text(error);
+                                            }
+                                            None => {}
+                                        }
+
+                                        let has_recording = self.state.lock().unwrap().demo_recordings.contains_key(*path);
+                                        if has_recording && ui.button("📈 Plot")
+                                            .on_hover_text("Show this demo in the viewangle timeline and radar")
+                                            .clicked()
+                                        {
+                                            self.state.lock().unwrap().show_demo_plots(path);
+                                        }
+
+                                        let has_detections = self.state.lock().unwrap().demo_detections
+                                            .get(*path)
+                                            .map_or(false, |detections| !detections.is_empty());
+                                        if has_detections && ui.button("🎬 Export VDM")
+                                            .on_hover_text("Write a .vdm next to the demo that skips to each detection")
+                                            .clicked()
+                                        {
                                             let mut state = self.state.lock().unwrap();
-                                            state.log_messages.push(format!("Analyzing demo for suspicious viewangles..."));
-                                            drop(state);
+                                            let result = match state.demo_detections.get(*path) {
+                                                Some(detections) => vdm::write_vdm(path, detections),
+                                                None => Err("No detections to export".to_string()),
+                                            };
+                                            match result {
+                                                Ok(vdm_path) => {
+                                                    state.copy_status = Some(format!("VDM saved to: {}", vdm_path.to_string_lossy()));
+                                                    state.log_messages.push(format!("VDM saved to: {}", vdm_path.to_string_lossy()));
+                                                }
+                                                Err(e) => {
+                                                    state.log_messages.push(format!("Error exporting VDM: {}", e));
+                                                }
+                                            }
+                                        }
 
-                                            // Use the analyze_demo_for_cheaters function
-                                            match analyze_demo_for_cheaters(&path_clone) {
-                                                Ok((detections, Some(output_file))) => {
-                                                    let mut state = self.state.lock().unwrap();
-                                                    state.log_messages.push(format!("Analysis complete! Found {} suspicious angle changes", detections.len()));
-                                                    
-                                                    // Print some details about the detections
-                                                    for (i, detection) in detections.iter().enumerate().take(5) { // Show first 5 only
-                                                        state.log_messages.push(format!(
-                                                            "Detection #{}: Player {} at tick {} - {}",
-                                                            i+1, detection.player, detection.tick, detection.algorithm
-                                                        ));
-                                                    }
-                                                    
-                                                    if detections.len() > 5 {
-                                                        state.log_messages.push(format!("... and {} more detections", detections.len() - 5));
-                                                    }
-                                                    
-                                                    state.copy_status = Some(format!("Viewangles data saved to: {}", output_file.to_string_lossy()));
-                                                    state.log_messages.push(format!("Viewangles data saved to: {}", output_file.to_string_lossy()));
-                                                    
-                                                    // Store the path of the last viewangles file
-                                                    state.last_viewangles_file = Some(output_file);
-                                                    
-                                                    drop(state);
-                                                    
-                                                    // Update demo analyses to reflect the new viewangles data
-                                                    self.update_demo_analyses();
-                                                },
-                                                Ok((detections, None)) => {
-                                                    let mut state = self.state.lock().unwrap();
-                                                    state.log_messages.push(format!("Analysis complete! Found {} suspicious angle changes", detections.len()));
-                                                    
-                                                    // Print some details about the detections
-                                                    for (i, detection) in detections.iter().enumerate().take(5) { // Show first 5 only
-                                                        state.log_messages.push(format!(
-                                                            "Detection #{}: Player {} at tick {} - {}",
-                                                            i+1, detection.player, detection.tick, detection.algorithm
-                                                        ));
+                                        // One evidence report per flagged player
+                                        if has_detections {
+                                            ui.menu_button("📄 Report", |ui| {
+                                                let flagged = {
+                                                    let state = self.state.lock().unwrap();
+                                                    flagged_players(state.demo_detections.get(*path).map(Vec::as_slice).unwrap_or(&[]))
+                                                };
+                                                for (steamid, name, count) in flagged {
+                                                    if ui.button(format!("{} ({} detections)", name, count)).clicked() {
+                                                        self.write_report(path.to_path_buf(), steamid, ctx);
+                                                        ui.close_menu();
                                                     }
-                                                    
-                                                    if detections.len() > 5 {
-                                                        state.log_messages.push(format!("... and {} more detections", detections.len() - 5));
+                                                }
+                                            });
+
+                                            // Mark a flagged player in the local list with their detections as proof
+                                            ui.menu_button("🏷 Mark", |ui| {
+                                                let (flagged, detections) = {
+                                                    let state = self.state.lock().unwrap();
+                                                    let detections = state.demo_detections.get(*path).cloned().unwrap_or_default();
+                                                    (flagged_players(&detections), detections)
+                                                };
+                                                for (steamid, name, count) in flagged {
+                                                    if ui.button(format!("{} ({} detections)", name, count)).clicked() {
+                                                        let proof = detections.iter()
+                                                            .filter(|detection| detection.player == steamid)
+                                                            .take(MAX_MARK_PROOF)
+                                                            .map(|detection| playerlist::demo_proof(path, detection.tick))
+                                                            .collect();
+                                                        let steamid32 = steamid_64_to_32(&steamid.to_string()).unwrap_or_else(|| steamid.to_string());
+                                                        self.open_mark_editor(&steamid32, &name, proof);
+                                                        ui.close_menu();
                                                     }
-                                                    
-                                                    state.copy_status = Some("Viewangles data not saved".to_string());
-                                                    state.log_messages.push("Viewangles data not saved".to_string());
-                                                    drop(state);
-                                                    
-                                                    // Update demo analyses to reflect the new viewangles data
-                                                    self.update_demo_analyses();
-                                                },
-                                                Err(e) => {
-                                                    let mut state = self.state.lock().unwrap();
-                                                    state.log_messages.push(format!("Error analyzing demo: {}", e));
                                                 }
-                                            }
+                                            });
                                         }
                                         
                                         // Add a button to open the file in explorer
@@ -2453,11 +2897,11 @@ impl DemoParserApp {
         }
         
         let search_lower = search.to_lowercase();
-        let (demo_list, output, steamid_to_usernames) = {
+        let (demo_list, output, name_histories) = {
             let state = self.state.lock().unwrap();
             (state.demo_list.clone(), 
              self.output.lock().unwrap().clone(),
-             state.steamid_to_usernames.clone())
+             state.name_histories.clone())
         };
 
         let mut results = Vec::new();
@@ -2497,10 +2941,10 @@ impl DemoParserApp {
             if let Some(players) = output.get(&path) {
                 for (username, steamid) in players {
                     // Get all known usernames for this steamid
-                    let known_usernames = steamid_to_usernames.get(steamid)
-                        .map(|names| names.iter()
+                    let known_usernames = name_histories.get(steamid)
+                        .map(|history| history.names()
                             .filter(|&n| n != username)
-                            .cloned()
+                            .map(str::to_string)
                             .collect::<Vec<_>>())
                         .unwrap_or_default();
 
@@ -2537,10 +2981,10 @@ impl DemoParserApp {
                     if username_match || steamid_match {
                         should_include = true;
                         // Always include known usernames when there's a match
-                        let all_known_usernames = steamid_to_usernames.get(steamid)
-                            .map(|names| names.iter()
+                        let all_known_usernames = name_histories.get(steamid)
+                            .map(|history| history.names()
                                 .filter(|&n| n != username)
-                                .cloned()
+                                .map(str::to_string)
                                 .collect::<Vec<_>>())
                             .unwrap_or_default();
                         
@@ -2588,58 +3032,252 @@ impl DemoParserApp {
         state.demo_browser_results = results;
     }
     
-    fn start_processing(&mut self) {
-        self.scroll_to_bottom = true;
-        let state = self.state.lock().unwrap().clone();
-        
-        // Reset counters
-        {
-            let mut state = self.state.lock().unwrap();
-            state.save_counter = 0;
-            state.processed_demos = 0;
-            state.failed_demos.clear();
-        }
-        
-        // Find all demo files
-        let pattern = state.demo_folder.join("*.dem");
-        let pattern_str = pattern.to_str().unwrap_or("*.dem");
-        
-        let all_demos: Vec<PathBuf> = match glob(pattern_str) {
-            Ok(paths) => paths.filter_map(|path| path.ok()).collect(),
-            Err(e) => {
-                let mut state = self.state.lock().unwrap();
-                state.log_messages.push(format!("Error: Invalid glob pattern: {}", e));
-                self.scroll_to_bottom = true;
-                return;
-            }
-        };
-        
-        if all_demos.is_empty() {
-            let mut state = self.state.lock().unwrap();
-            state.log_messages.push("No demo files found!".to_string());
-            self.scroll_to_bottom = true;
-            return;
-        }
-        
-        // Get current output
-        let current_output = load_existing_results(&state.output_path);
-        
-        // Filter out already processed demos
-        let demos_to_process: Vec<PathBuf> = all_demos
-            .into_iter()
-            .filter(|demo| !current_output.contains_key(demo))
+    /// Queue demos for cheat analysis, results arrive in `update` as each one finishes
+    fn queue_demo_checks(&mut self, demos: Vec<PathBuf>) {
+        let mut state = self.state.lock().unwrap();
+        let demos: Vec<PathBuf> = demos.into_iter()
+            .filter(|demo| !matches!(state.demo_check_status.get(demo), Some(CheckStatus::Queued | CheckStatus::Running(_))))
             .collect();
-        
-        if demos_to_process.is_empty() {
-            let mut state = self.state.lock().unwrap();
-            state.log_messages.push("No new demos to process!".to_string());
-            self.scroll_to_bottom = true;
+        if demos.is_empty() {
             return;
         }
+        for demo in &demos {
+            state.demo_check_status.insert(demo.clone(), CheckStatus::Queued);
+        }
+        state.checks_total += demos.len();
+        state.log_messages.push(format!("Queued {} demos for cheat analysis", demos.len()));
+        self.scroll_to_bottom = true;
+        let tf2_folder = state.tf2_folder.clone();
+        drop(state);
+
+        let mut queue = self.check_queue.lock().unwrap();
+        queue.jobs.extend(demos);
+        let threadcount = thread::available_parallelism().map_or(1, |count| count.get()).min(MAX_CHECK_WORKERS);
+        let new_workers = threadcount.saturating_sub(queue.workers).min(queue.jobs.len());
+        queue.workers += new_workers;
+        drop(queue);
+
+        for _ in 0..new_workers {
+            let check_queue = self.check_queue.clone();
+            let sender = self.check_sender.clone();
+            let tf2_folder = tf2_folder.clone();
+
+            let handle = thread::spawn(move || loop {
+                // Workers leave under the queue lock, so demos queued meanwhile always find one
+                let demo = {
+                    let mut queue = check_queue.lock().unwrap();
+                    match queue.jobs.pop_front() {
+                        Some(demo) => demo,
+                        None => {
+                            queue.workers -= 1;
+                            break;
+                        }
+                    }
+                };
+                let _ = sender.send(CheckUpdate::Started(demo.clone()));
+                let demo_name = demo.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Unknown".to_string());
+                let result = analyze_demo_for_cheaters(&demo, tf2_folder.as_deref(), &|progress| {
+                    let _ = sender.send(CheckUpdate::Progress(demo.clone(), progress));
+                }, &|message| {
+                    let _ = sender.send(CheckUpdate::Log(format!("{}: {}", demo_name, message)));
+                });
+                let _ = sender.send(CheckUpdate::Finished(demo, result));
+            });
+
+            self.worker_handles.push(handle);
+        }
+    }
+
+    /// Write the evidence report of one player in a checked demo in the background and open it
+    fn write_report(&self, demo: PathBuf, steamid: u64, ctx: &egui::Context) {
+        let (known_names, viewangles_file, detections) = {
+            let mut state = self.state.lock().unwrap();
+            state.copy_status = Some("Writing report...".to_string());
+            let known_names = steamid_64_to_32(&steamid.to_string())
+                .and_then(|id32| state.name_histories.get(&id32))
+                .map(|history| history.names().map(str::to_string).collect())
+                .unwrap_or_default();
+            (known_names, state.demo_viewangles_files.get(&demo).cloned(), state.demo_detections.get(&demo).cloned().unwrap_or_default())
+        };
+        let state_clone = self.state.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let input = report::ReportInput {
+                demo_path: &demo,
+                steamid,
+                viewangles_file: viewangles_file.as_deref(),
+                known_names,
+            };
+            let result = report::write_report(&input, &detections);
+            let mut state = state_clone.lock().unwrap();
+            match result {
+                Ok(report_path) => {
+                    state.copy_status = Some(format!("Report saved to: {}", report_path.to_string_lossy()));
+                    state.log_messages.push(format!("Report saved to: {}", report_path.to_string_lossy()));
+                    if let Err(e) = open_file(&report_path) {
+                        state.log_messages.push(e);
+                    }
+                }
+                Err(e) => {
+                    state.copy_status = None;
+                    state.log_messages.push(format!("Error writing report: {}", e));
+                }
+            }
+            drop(state);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Drop demos that haven't started yet, running checks are left to finish
+    fn cancel_demo_checks(&mut self) {
+        let cancelled: Vec<PathBuf> = self.check_queue.lock().unwrap().jobs.drain(..).collect();
+        let mut state = self.state.lock().unwrap();
+        for demo in &cancelled {
+            state.demo_check_status.remove(demo);
+        }
+        state.checks_total -= cancelled.len();
+        state.log_messages.push(format!("Cancelled {} queued demo checks", cancelled.len()));
+        if state.checks_done >= state.checks_total {
+            state.checks_total = 0;
+            state.checks_done = 0;
+        }
+        self.scroll_to_bottom = true;
+    }
+
+    fn handle_check_result(&mut self, demo: PathBuf, result: CheckResult) {
+        let demo_name = demo.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+        let mut state = self.state.lock().unwrap();
+        state.checks_done += 1;
+        self.scroll_to_bottom = true;
+
+        match result {
+            Ok((detections, output_file, recording)) => {
+                state.demo_check_status.insert(demo.clone(), CheckStatus::Done(detections.len()));
+                let mut per_algorithm: BTreeMap<&str, usize> = BTreeMap::new();
+                for detection in &detections {
+                    *per_algorithm.entry(detection.algorithm.as_str()).or_default() += 1;
+                }
+                let counts: Vec<String> = per_algorithm.iter()
+                    .map(|(algorithm, count)| format!("{} {}", count, algorithm))
+                    .collect();
+                if counts.is_empty() {
+                    state.log_messages.push(format!("Analysis of {} complete! No detections", demo_name));
+                } else {
+                    state.log_messages.push(format!("Analysis of {} complete! Found {}", demo_name, counts.join(", ")));
+                }
+
+                // Print some details about the detections
+                for (i, detection) in detections.iter().enumerate().take(5) { // Show first 5 only
+                    state.log_messages.push(format!(
+                        "Detection #{}: Player {} at tick {} - {}",
+                        i+1, detection.player, detection.tick, detection.algorithm
+                    ));
+                    if let Some(timestamp) = &detection.timestamp {
+                        state.log_messages.push(format!("    {} - {}", timestamp.elapsed, timestamp.playdemo));
+                    }
+                }
+
+                if detections.len() > 5 {
+                    state.log_messages.push(format!("... and {} more detections", detections.len() - 5));
+                }
+
+                match output_file {
+                    Some(output_file) => {
+                        state.copy_status = Some(format!("Viewangles data saved to: {}", output_file.to_string_lossy()));
+                        state.log_messages.push(format!("Viewangles data saved to: {}", output_file.to_string_lossy()));
+
+                        // Store the path of the last viewangles file
+                        state.demo_viewangles_files.insert(demo.clone(), output_file.clone());
+                        state.last_viewangles_file = Some(output_file);
+                    }
+                    None => {
+                        state.copy_status = Some("Viewangles data not saved".to_string());
+                        state.log_messages.push("Viewangles data not saved".to_string());
+                    }
+                }
+
+                // Plots open on the first finished demo, the rest are picked with their Plot button
+                state.demo_detections.insert(demo.clone(), detections);
+                state.keep_recording(demo.clone(), Arc::new(recording));
+                let showing = state.viewangle_timeline.as_ref().map(|timeline| timeline.demo_path.clone());
+                if showing.is_none() || showing.as_ref() == Some(&demo) {
+                    state.show_demo_plots(&demo);
+                }
+            }
+            Err(e) => {
+                state.demo_check_status.insert(demo, CheckStatus::Failed(e.clone()));
+                state.log_messages.push(format!("Error analyzing {}: {}", demo_name, e));
+            }
+        }
+
+        if state.checks_done >= state.checks_total {
+            let checked = state.checks_done;
+            state.log_messages.push(format!("Cheat analysis complete! Checked {} demos", checked));
+            state.checks_total = 0;
+            state.checks_done = 0;
+        }
+        drop(state);
+
+        // Update demo analyses to reflect the new viewangles data
+        self.update_demo_analyses();
+    }
+
+    fn start_processing(&mut self) {
+        self.scroll_to_bottom = true;
+        let state = self.state.lock().unwrap().clone();
+        
+        // Reset counters
+        {
+            let mut state = self.state.lock().unwrap();
+            state.save_counter = 0;
+            state.processed_demos = 0;
+            state.failed_demos.clear();
+        }
+        
+        // Find all demo files
+        let pattern = state.demo_folder.join("*.dem");
+        let pattern_str = pattern.to_str().unwrap_or("*.dem");
+        
+        let all_demos: Vec<PathBuf> = match glob(pattern_str) {
+            Ok(paths) => paths.filter_map(|path| path.ok()).collect(),
+            Err(e) => {
+                let mut state = self.state.lock().unwrap();
+                state.log_messages.push(format!("Error: Invalid glob pattern: {}", e));
+                self.scroll_to_bottom = true;
+                return;
+            }
+        };
+        
+        if all_demos.is_empty() {
+            let mut state = self.state.lock().unwrap();
+            state.log_messages.push("No demo files found!".to_string());
+            self.scroll_to_bottom = true;
+            return;
+        }
+        
+        // Get current output
+        let current_output = load_existing_results(&state.output_path);
+        
+        // Filter out already processed demos
+        let demos_to_process: Vec<PathBuf> = all_demos
+            .into_iter()
+            .filter(|demo| !current_output.contains_key(demo))
+            .collect();
+        
+        if demos_to_process.is_empty() {
+            let mut state = self.state.lock().unwrap();
+            state.log_messages.push("No new demos to process!".to_string());
+            self.scroll_to_bottom = true;
+            return;
+        }
+        
+        // Initialize output with existing results
+        *self.output.lock().unwrap() = current_output;
         
-        // Initialize output with existing results
-        *self.output.lock().unwrap() = current_output;
-        
         let (result_tx, result_rx) = channel();
         self.result_receiver = Some(result_rx);
         
@@ -2688,52 +3326,705 @@ impl DemoParserApp {
     fn load_player_list(&self) {
         let state_clone = self.state.clone();
         thread::spawn(move || {
-            let url = {
+            let sources: Vec<playerlist::ListSource> = {
                 let state = state_clone.lock().unwrap();
-                state.player_list_url.clone()
+                state.player_lists.sources.iter().filter(|source| source.enabled).cloned().collect()
             };
-            
-            // GitHub requires raw content URL
-            let raw_url = url.replace("github.com", "raw.githubusercontent.com")
-                .replace("/blob/", "/");
-            
-            match reqwest::blocking::get(&raw_url) {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        let player_list: PlayerListFile = match response.json() {
-                            Ok(list) => list,
-                            Err(e) => {
-                                let mut state = state_clone.lock().unwrap();
-                                state.log_messages.push(format!("Failed to parse player list: {}", e));
-                                return;
-                            }
-                        };
-                        
+
+            // Cached copies first so marks show up right away, and offline
+            let mut cached = HashMap::new();
+            for source in &sources {
+                if let Some(list) = playerlist::load_cached(&source.url) {
+                    let mut state = state_clone.lock().unwrap();
+                    state.player_lists.set_list(list.clone());
+                    cached.insert(source.url.clone(), list);
+                }
+            }
+            state_clone.lock().unwrap().player_list_loaded = true;
+            if !cached.is_empty() {
+                DemoParserApp::update_player_stats_for(&state_clone);
+            }
+
+            // Then ask each source for changes since the cached copy
+            for source in sources {
+                match playerlist::fetch(&source.url, cached.get(&source.url)) {
+                    Ok(list) => {
                         let mut state = state_clone.lock().unwrap();
-                        state.player_list = player_list.players;
-                        state.player_list_loaded = true;
-                        state.log_messages.push("Player list loaded successfully".to_string());
-                        
-                        // Save URL to settings
-                        let mut settings = AppSettings::load();
-                        settings.player_list_url = url;
-                        settings.save();
-                        
-                        // Update player stats
-                        drop(state);
-                        DemoParserApp::update_player_stats_for(&state_clone);
-                    } else {
+                        state.log_messages.push(format!("Loaded {} players from {}", list.players.len(), source.name));
+                        state.player_lists.set_list(list);
+                    }
+                    Err(e) => {
                         let mut state = state_clone.lock().unwrap();
-                        state.log_messages.push(format!("Failed to download player list: {}", response.status()));
+                        state.player_lists.set_error(&source.url, e.clone());
+                        if cached.contains_key(&source.url) {
+                            state.log_messages.push(format!("{}: {}, using the cached copy", source.name, e));
+                        } else {
+                            state.log_messages.push(format!("{}: {}", source.name, e));
+                        }
                     }
                 }
-                Err(e) => {
-                    let mut state = state_clone.lock().unwrap();
-                    state.log_messages.push(format!("Failed to download player list: {}", e));
+            }
+
+            state_clone.lock().unwrap().log_messages.push("Player lists loaded".to_string());
+
+            // Update player stats
+            DemoParserApp::update_player_stats_for(&state_clone);
+        });
+    }
+
+    /// Write players to a list file, with the demos they were seen in as proof
+    fn export_player_list(&self, players: &[PlayerStats], path: &Path) -> Result<usize, String> {
+        let (all_output_paths, attribute, format, demo_detections) = {
+            let state = self.state.lock().unwrap();
+            (
+                state.all_output_paths.clone(),
+                state.export_attribute,
+                state.export_format,
+                state.demo_detections.clone(),
+            )
+        };
+
+        // Demos each player was seen in, across every dump
+        let wanted: HashSet<&str> = players.iter().map(|player| player.steamid.as_str()).collect();
+        let mut seen: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for output_path in &all_output_paths {
+            if output_path.exists() {
+                for (demo, demo_players) in load_existing_results(output_path) {
+                    for steamid in demo_players.values() {
+                        if wanted.contains(steamid.as_str()) {
+                            seen.entry(steamid.clone()).or_default().push(demo.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        let modified = |demo: &Path| {
+            fs::metadata(demo)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_secs())
+        };
+        let export = players.iter()
+            .map(|player| {
+                let mut demos: Vec<(Option<u64>, PathBuf)> = seen.remove(&player.steamid).unwrap_or_default()
+                    .into_iter()
+                    .map(|demo| (modified(&demo), demo))
+                    .collect();
+                // Newest first
+                demos.sort();
+                demos.dedup();
+                demos.reverse();
+
+                let steamid64 = steamid_32_to_64(&player.steamid).and_then(|id| id.parse::<u64>().ok());
+                let mut proof = Vec::new();
+                for (_, demo) in demos.iter().take(MAX_EXPORT_DEMOS) {
+                    // Detections pin the exact moment, otherwise the demo itself is the reference
+                    let ticks: Vec<u32> = demo_detections.get(demo)
+                        .map(|detections| detections.iter()
+                            .filter(|detection| Some(detection.player) == steamid64)
+                            .map(|detection| detection.tick)
+                            .take(MAX_MARK_PROOF)
+                            .collect())
+                        .unwrap_or_default();
+                    if ticks.is_empty() {
+                        proof.push(demo.to_string_lossy().to_string());
+                    } else {
+                        proof.extend(ticks.into_iter().map(|tick| playerlist::demo_proof(demo, tick)));
+                    }
+                }
+
+                playerlist::ExportPlayer {
+                    steamid: player.steamid.clone(),
+                    name: player.username.clone(),
+                    last_seen: demos.first().and_then(|(time, _)| *time),
+                    proof,
                 }
+            })
+            .collect();
+
+        let title = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let list = playerlist::build_export(&title, export, attribute);
+        playerlist::write_list(&list, path, format)?;
+        Ok(players.len())
+    }
+
+    /// Window with everything known about one player, opened from the Players tab
+    fn render_player_detail(&mut self, ctx: &egui::Context) {
+        // Demos of a player whose window was closed or replaced aren't needed anymore
+        let player_detail = self.state.lock().unwrap().player_detail.clone();
+        if player_detail != self.info_queue_player {
+            self.cancel_demo_infos();
+            self.info_queue_player = player_detail;
+        }
+
+        let (steamid, history, marks, infos, detections, missing, tf2_folder, co_players, name_histories, player_lists, mut cheaters_only, mut min_together) = {
+            let state = self.state.lock().unwrap();
+            let steamid = match &state.player_detail {
+                Some(steamid) => steamid.clone(),
+                None => return,
+            };
+            let history = state.name_histories.get(&steamid).cloned().unwrap_or_default();
+            let marks = state.player_stats.iter()
+                .find(|player| player.steamid == steamid)
+                .map(|player| player.marks.clone())
+                .unwrap_or_default();
+            let demos: HashSet<&PathBuf> = history.names.iter().flat_map(|used| used.demos.iter().map(|(demo, _)| demo)).collect();
+            let infos: HashMap<PathBuf, Result<encounters::DemoInfo, String>> = demos.iter()
+                .filter_map(|demo| state.demo_infos.get(*demo).map(|info| ((*demo).clone(), info.clone())))
+                .collect();
+            let missing: Vec<PathBuf> = demos.iter()
+                .filter(|demo| !state.demo_infos.contains_key(demo.as_path()) && !state.demo_infos_loading.contains(demo.as_path()))
+                .map(|demo| (*demo).clone())
+                .collect();
+            // Only demos checked this session have detections
+            let steamid64 = steamid_32_to_64(&steamid).and_then(|id| id.parse::<u64>().ok());
+            let detections: HashMap<PathBuf, Vec<Detection>> = demos.iter()
+                .filter_map(|demo| {
+                    let found = state.demo_detections.get(*demo)?;
+                    let own = found.iter().filter(|detection| Some(detection.player) == steamid64).cloned().collect();
+                    Some(((*demo).clone(), own))
+                })
+                .collect();
+            let own_demos: Vec<PathBuf> = demos.iter().map(|demo| (*demo).clone()).collect();
+            let co_players = relations::co_players(&steamid, &own_demos, &state.demo_players, &infos);
+            (
+                steamid,
+                history,
+                marks,
+                infos,
+                detections,
+                missing,
+                state.tf2_folder.clone(),
+                co_players,
+                state.name_histories.clone(),
+                state.player_lists.merged(),
+                state.relations_cheaters_only,
+                state.relations_min_demos,
+            )
+        };
+        if !missing.is_empty() {
+            self.load_demo_infos(missing, ctx);
+        }
+        let name = history.current().unwrap_or(&steamid).to_string();
+        let steamid64 = steamid_32_to_64(&steamid);
+        let confirmed_alts = self.state.lock().unwrap().alt_reviews.alts_of(&steamid);
+
+        // Every demo with the name used in it, newest first
+        let mut appearances: Vec<(&PathBuf, u64, &str)> = history.names.iter()
+            .flat_map(|used| used.demos.iter().map(move |(demo, seen)| (demo, *seen, used.name.as_str())))
+            .collect();
+        appearances.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut algorithms: Vec<(String, usize)> = Vec::new();
+        for detection in detections.values().flatten() {
+            match algorithms.iter_mut().find(|(algorithm, _)| *algorithm == detection.algorithm) {
+                Some((_, count)) => *count += 1,
+                None => algorithms.push((detection.algorithm.clone(), 1)),
             }
+        }
+        algorithms.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let mut open = true;
+        let mut mark = false;
+        let mut open_demo: Option<PathBuf> = None;
+        let mut convert: Option<PathBuf> = None;
+        let mut copied: Option<String> = None;
+        let mut show_player: Option<String> = None;
+        egui::Window::new(format!("Player: {}", name))
+            .id(egui::Id::new("player_detail"))
+            .open(&mut open)
+            .default_width(720.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&steamid).monospace());
+                    if let Some(steamid64) = &steamid64 {
+                        ui.label(egui::RichText::new(steamid64).monospace().weak());
+                    }
+                    ui.label(format!("{} demos", appearances.len()));
+                    for source_mark in &marks {
+                        if playerlist::mark_badge(ui, source_mark).clicked() {
+                            if let Some(proof) = source_mark.proof.first() {
+                                let _ = webbrowser::open(proof);
+                            }
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if let Some(steamid64) = &steamid64 {
+                        let profile = format!("https://steamcommunity.com/profiles/{}", steamid64);
+                        if ui.button("🔗 Copy Profile URL").clicked() {
+                            if let Ok(mut clipboard) = Clipboard::new() {
+                                if clipboard.set_text(&profile).is_ok() {
+                                    copied = Some("Profile URL copied to clipboard!".to_string());
+                                }
+                            }
+                        }
+                        if ui.button("🌐 Open Profile").clicked() {
+                            let _ = webbrowser::open(&profile);
+                        }
+                    }
+                    if ui.button("🏷 Mark").on_hover_text("Mark in the local player list").clicked() {
+                        mark = true;
+                    }
+                });
+                if !confirmed_alts.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Confirmed alts:");
+                        for alt in &confirmed_alts {
+                            let alt_name = name_histories.get(alt)
+                                .and_then(|history| history.current())
+                                .unwrap_or(alt);
+                            if ui.link(alt_name).clicked() {
+                                show_player = Some(alt.clone());
+                            }
+                        }
+                    });
+                }
+                ui.separator();
+
+                egui::CollapsingHeader::new("Name history")
+                    .id_source("player_detail_history")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        history::show_timeline(ui, &history);
+                        ui.add_space(4.0);
+                        for used in &history.names {
+                            egui::CollapsingHeader::new(format!(
+                                "{}  ({} - {}, {} demos)",
+                                used.name,
+                                history::format_date(used.first_seen),
+                                history::format_date(used.last_seen),
+                                used.demos.len()
+                            ))
+                            .id_source(("player_detail_name", &used.name))
+                            .show(ui, |ui| {
+                                for (demo, _) in &used.demos {
+                                    ui.label(egui::RichText::new(demo.to_string_lossy()).small());
+                                }
+                            });
+                        }
+                    });
+
+                egui::CollapsingHeader::new("Detections")
+                    .id_source("player_detail_detections")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        if detections.is_empty() {
+                            ui.label("None of this player's demos were checked yet, use the Demo Checker");
+                            return;
+                        }
+                        let flagged = detections.values().filter(|found| !found.is_empty()).count();
+                        ui.label(format!(
+                            "Flagged in {} of {} checked demos, {} not checked",
+                            flagged,
+                            detections.len(),
+                            appearances.len() - detections.len()
+                        ));
+                        for (algorithm, count) in &algorithms {
+                            ui.label(format!("  {}: {}", algorithm, count));
+                        }
+                    });
+
+                egui::CollapsingHeader::new("Played with / against")
+                    .id_source("player_detail_relations")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut cheaters_only, "Only listed cheaters");
+                            ui.label("At least");
+                            ui.add(egui::DragValue::new(&mut min_together).clamp_range(1..=100));
+                            ui.label("demos together");
+                        });
+                        let shown: Vec<&relations::CoPlayer> = co_players.iter()
+                            .filter(|co_player| co_player.together >= min_together)
+                            .filter(|co_player| !cheaters_only || player_lists.get(&co_player.steamid)
+                                .map_or(false, |merged| merged.marks.iter()
+                                    .any(|mark| mark.attributes.contains(&playerlist::PlayerAttribute::Cheater))))
+                            .take(MAX_CO_PLAYERS)
+                            .collect();
+                        if shown.is_empty() {
+                            ui.label("Nobody matches");
+                            return;
+                        }
+                        egui::ScrollArea::vertical()
+                            .id_source("player_detail_relations_scroll")
+                            .max_height(240.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("player_detail_relations_grid")
+                                    .striped(true)
+                                    .num_columns(6)
+                                    .show(ui, |ui| {
+                                        ui.strong("Player");
+                                        ui.strong("SteamID");
+                                        ui.strong("Together");
+                                        ui.strong("Same team").on_hover_text("Only demos whose teams are loaded count");
+                                        ui.strong("Opposite");
+                                        ui.strong("Lists");
+                                        ui.end_row();
+
+                                        for co_player in shown {
+                                            let co_name = name_histories.get(&co_player.steamid)
+                                                .and_then(|history| history.current())
+                                                .unwrap_or(&co_player.steamid);
+                                            if ui.add(egui::Label::new(co_name).sense(egui::Sense::click()))
+                                                .on_hover_text("Show player details")
+                                                .clicked()
+                                            {
+                                                show_player = Some(co_player.steamid.clone());
+                                            }
+                                            ui.label(egui::RichText::new(&co_player.steamid).monospace());
+                                            ui.label(co_player.together.to_string());
+                                            ui.label(co_player.same_team.to_string());
+                                            ui.label(co_player.opposite_team.to_string());
+                                            ui.horizontal(|ui| {
+                                                if let Some(merged) = player_lists.get(&co_player.steamid) {
+                                                    for source_mark in &merged.marks {
+                                                        playerlist::mark_badge(ui, source_mark);
+                                                    }
+                                                }
+                                            });
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    });
+
+                egui::CollapsingHeader::new("Encounters")
+                    .id_source("player_detail_encounters")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical()
+                            .id_source("player_detail_encounters_scroll")
+                            .max_height(320.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("player_detail_encounters_grid")
+                                    .striped(true)
+                                    .num_columns(8)
+                                    .show(ui, |ui| {
+                                        for header in ["Date", "Name", "Map", "Server", "Side", "Length", "Detections", ""] {
+                                            ui.strong(header);
+                                        }
+                                        ui.end_row();
+
+                                        for (demo, time, used) in &appearances {
+                                            ui.label(history::format_date(*time))
+                                                .on_hover_text(demo.to_string_lossy());
+                                            ui.label(*used);
+                                            match infos.get(*demo) {
+                                                Some(Ok(info)) => {
+                                                    ui.label(&info.map);
+                                                    ui.label(&info.server);
+                                                    let relation = info.relation(&steamid);
+                                                    ui.colored_label(relation.color(), relation.label())
+                                                        .on_hover_text(format!("Recorded by {}", info.recorder));
+                                                    ui.label(encounters::format_duration(info.duration));
+                                                }
+                                                Some(Err(e)) => {
+                                                    ui.colored_label(egui::Color32::RED, "Failed").on_hover_text(e);
+                                                    for _ in 0..3 {
+                                                        ui.label("");
+                                                    }
+                                                }
+                                                None => {
+                                                    ui.spinner();
+                                                    for _ in 0..3 {
+                                                        ui.label("");
+                                                    }
+                                                }
+                                            }
+                                            match detections.get(*demo) {
+                                                Some(found) if !found.is_empty() => {
+                                                    ui.colored_label(egui::Color32::from_rgb(235, 80, 80), found.len().to_string());
+                                                }
+                                                Some(_) => {
+                                                    ui.label("0");
+                                                }
+                                                None => {
+                                                    ui.weak("not checked");
+                                                }
+                                            }
+                                            ui.horizontal(|ui| {
+                                                if ui.small_button("📂").on_hover_text("Open demo").clicked() {
+                                                    open_demo = Some((*demo).clone());
+                                                }
+                                                if ui.small_button("🎬").on_hover_text("Convert to replay").clicked() {
+                                                    convert = Some((*demo).clone());
+                                                }
+                                                if ui.small_button("▶").on_hover_text("Copy play command").clicked() {
+                                                    if let Ok(mut clipboard) = Clipboard::new() {
+                                                        if clipboard.set_text(format!("playdemo {}", cheater_detection::playdemo_path(demo))).is_ok() {
+                                                            copied = Some("Play command copied to clipboard!".to_string());
+                                                        }
+                                                    }
+                                                }
+                                            });
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    });
+            });
+
+        if let Some(demo) = open_demo {
+            if let Err(e) = open_file(&demo) {
+                self.state.lock().unwrap().log_messages.push(format!("Failed to open demo: {}", e));
+            }
+        }
+        if let Some(demo) = convert {
+            let name = demo.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let result = match &tf2_folder {
+                Some(tf2_folder) => self.runtime.block_on(async { convert_to_replay(&demo, tf2_folder, &name).await })
+                    .map(|_| format!("Successfully converted {} to replay!", name))
+                    .map_err(|e| format!("Failed to convert demo: {}", e)),
+                None => Err("Please select your TF2 folder in the Parser tab first!".to_string()),
+            };
+            let mut state = self.state.lock().unwrap();
+            match result {
+                Ok(message) => {
+                    state.log_messages.push(message);
+                    state.copy_status = Some("Demo converted to replay!".to_string());
+                }
+                Err(message) => state.log_messages.push(message),
+            }
+        }
+        if let Some(copied) = copied {
+            self.state.lock().unwrap().copy_status = Some(copied);
+        }
+        if mark {
+            self.open_mark_editor(&steamid, &name, Vec::new());
+        }
+        let mut state = self.state.lock().unwrap();
+        state.relations_cheaters_only = cheaters_only;
+        state.relations_min_demos = min_together;
+        if !open {
+            state.player_detail = None;
+        } else if show_player.is_some() {
+            state.player_detail = show_player;
+        }
+    }
+
+    /// Parse demos for the player detail window in the background, a few at a time like the checks
+    fn load_demo_infos(&self, demos: Vec<PathBuf>, ctx: &egui::Context) {
+        self.state.lock().unwrap().demo_infos_loading.extend(demos.iter().cloned());
+
+        let mut queue = self.info_queue.lock().unwrap();
+        queue.jobs.extend(demos);
+        let threadcount = thread::available_parallelism().map_or(1, |count| count.get()).min(MAX_CHECK_WORKERS);
+        let new_workers = threadcount.saturating_sub(queue.workers).min(queue.jobs.len());
+        queue.workers += new_workers;
+        drop(queue);
+
+        for _ in 0..new_workers {
+            let info_queue = self.info_queue.clone();
+            let state_clone = self.state.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || loop {
+                let demo = {
+                    let mut queue = info_queue.lock().unwrap();
+                    match queue.jobs.pop_front() {
+                        Some(demo) => demo,
+                        None => {
+                            queue.workers -= 1;
+                            break;
+                        }
+                    }
+                };
+                let info = encounters::read_demo_info(&demo);
+                let mut state = state_clone.lock().unwrap();
+                state.demo_infos_loading.remove(&demo);
+                state.demo_infos.insert(demo, info);
+                drop(state);
+                ctx.request_repaint();
+            });
+        }
+    }
+
+    /// Drop demo infos that haven't started parsing yet
+    fn cancel_demo_infos(&self) {
+        let cancelled: Vec<PathBuf> = self.info_queue.lock().unwrap().jobs.drain(..).collect();
+        let mut state = self.state.lock().unwrap();
+        for demo in &cancelled {
+            state.demo_infos_loading.remove(demo);
+        }
+    }
+
+    /// Run the alt account detector over the dumps in the background
+    fn find_alts(&self) {
+        let (name_histories, demo_players, reviews, detections) = {
+            let mut state = self.state.lock().unwrap();
+            if state.alt_search_running {
+                return;
+            }
+            state.alt_search_running = true;
+            // Detection counts per algorithm of everyone in the demos checked this session
+            let mut detections: HashMap<String, HashMap<String, usize>> = HashMap::new();
+            for detection in state.demo_detections.values().flatten() {
+                if let Some(steamid) = steamid_64_to_32(&detection.player.to_string()) {
+                    *detections.entry(steamid).or_default().entry(detection.algorithm.clone()).or_default() += 1;
+                }
+            }
+            (state.name_histories.clone(), state.demo_players.clone(), state.alt_reviews.clone(), detections)
+        };
+        let state_clone = self.state.clone();
+        thread::spawn(move || {
+            let input = alts::AltInput {
+                name_histories: &name_histories,
+                demo_players: &demo_players,
+                detections: &detections,
+            };
+            let clusters = alts::find_alt_clusters(&input, &reviews);
+            let mut state = state_clone.lock().unwrap();
+            state.log_messages.push(format!("Found {} possible alt account groups", clusters.len()));
+            state.alt_clusters = clusters;
+            state.alt_search_running = false;
         });
     }
+
+    /// Candidate alt clusters for a reviewer to confirm or reject
+    fn render_alt_window(&mut self, ctx: &egui::Context) {
+        let (mut show_alts, clusters, running, name_histories, player_lists, demo_counts) = {
+            let state = self.state.lock().unwrap();
+            if !state.show_alts {
+                return;
+            }
+            let members: HashSet<&String> = state.alt_clusters.iter().flat_map(|cluster| &cluster.members).collect();
+            let demo_counts: HashMap<String, usize> = state.player_stats.iter()
+                .filter(|player| members.contains(&player.steamid))
+                .map(|player| (player.steamid.clone(), player.demo_count))
+                .collect();
+            (
+                state.show_alts,
+                state.alt_clusters.clone(),
+                state.alt_search_running,
+                state.name_histories.clone(),
+                state.player_lists.merged(),
+                demo_counts,
+            )
+        };
+        let name_of = |steamid: &str| -> String {
+            name_histories.get(steamid)
+                .and_then(|history| history.current())
+                .unwrap_or(steamid)
+                .to_string()
+        };
+
+        let mut search = false;
+        let mut confirm: Option<Vec<String>> = None;
+        let mut reject: Option<Vec<String>> = None;
+        let mut show_player: Option<String> = None;
+        egui::Window::new("🕵 Alt Account Candidates")
+            .id(egui::Id::new("alt_candidates"))
+            .open(&mut show_alts)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if running {
+                        ui.spinner();
+                        ui.label("Looking for alts...");
+                    } else if ui.button("🔄 Search Again").clicked() {
+                        search = true;
+                    }
+                    ui.label(format!("{} candidate groups", clusters.len()));
+                });
+                ui.label(egui::RichText::new(
+                    "Accounts that were ever in the same demo are never grouped. Detection profiles only cover demos checked this session."
+                ).small().weak());
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_source("alt_candidates_scroll")
+                    .show(ui, |ui| {
+                        for cluster in &clusters {
+                            ui.group(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.strong(format!("{} accounts", cluster.members.len()));
+                                    ui.label(format!("score {:.1}", cluster.score()));
+                                    if cluster.confirmed {
+                                        ui.colored_label(egui::Color32::from_rgb(90, 200, 110), "✔ Confirmed");
+                                    } else if ui.button("✔ Confirm").on_hover_text("Remember these accounts as one person").clicked() {
+                                        confirm = Some(cluster.members.clone());
+                                    }
+                                    if ui.button("✖ Reject").on_hover_text("Never group these accounts again").clicked() {
+                                        reject = Some(cluster.members.clone());
+                                    }
+                                });
+                                for steamid in &cluster.members {
+                                    ui.horizontal(|ui| {
+                                        if ui.add(egui::Label::new(name_of(steamid)).sense(egui::Sense::click()))
+                                            .on_hover_text("Show player details")
+                                            .clicked()
+                                        {
+                                            show_player = Some(steamid.clone());
+                                        }
+                                        ui.label(egui::RichText::new(steamid).monospace().weak());
+                                        ui.label(format!("{} demos", demo_counts.get(steamid).copied().unwrap_or(0)));
+                                        if let Some(merged) = player_lists.get(steamid) {
+                                            for source_mark in &merged.marks {
+                                                playerlist::mark_badge(ui, source_mark);
+                                            }
+                                        }
+                                    });
+                                }
+                                egui::CollapsingHeader::new("Why")
+                                    .id_source(("alt_cluster_links", &cluster.members))
+                                    .show(ui, |ui| {
+                                        for link in &cluster.links {
+                                            let reasons: Vec<String> = link.signals.iter().map(alts::Signal::describe).collect();
+                                            ui.label(format!("{} ↔ {}: {}", name_of(&link.a), name_of(&link.b), reasons.join(", ")));
+                                        }
+                                    });
+                            });
+                        }
+                    });
+            });
+
+        let confirm_or_reject = confirm.is_some() || reject.is_some();
+        let mut state = self.state.lock().unwrap();
+        state.show_alts = show_alts;
+        if let Some(steamid) = show_player {
+            state.player_detail = Some(steamid);
+        }
+        if let Some(members) = confirm {
+            state.alt_reviews.confirm(&members);
+            for cluster in state.alt_clusters.iter_mut().filter(|cluster| cluster.members == members) {
+                cluster.confirmed = true;
+            }
+        }
+        if let Some(members) = reject {
+            state.alt_reviews.reject(&members);
+            state.alt_reviews.confirmed.retain(|group| !members.iter().all(|member| group.contains(member)));
+            state.alt_clusters.retain(|cluster| cluster.members != members);
+        }
+        if confirm_or_reject {
+            if let Err(e) = state.alt_reviews.save() {
+                state.log_messages.push(e);
+            }
+        }
+        drop(state);
+        if search {
+            self.find_alts();
+        }
+    }
+
+    /// Open the local list editor for a player, `steamid` is Steam32
+    fn open_mark_editor(&self, steamid: &str, name: &str, proof: Vec<String>) {
+        let mut state = self.state.lock().unwrap();
+        let editor = playerlist::MarkEditor::new(steamid, name, state.player_lists.local_entry(steamid), proof);
+        state.mark_editor = Some(editor);
+    }
+
+    /// Save the list sources and re-merge what's already downloaded
+    fn save_player_lists(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.player_lists.merge();
+        let mut settings = AppSettings::load();
+        settings.player_lists = state.player_lists.sources.clone();
+        settings.save();
+        drop(state);
+        self.update_player_stats();
+    }
     
     fn update_player_stats(&self) {
         let state_clone = self.state.clone();
@@ -2743,54 +4034,50 @@ impl DemoParserApp {
     }
     
     fn update_player_stats_for(state_clone: &Arc<Mutex<AppState>>) {
-        let (all_output_paths, player_list) = {
+        let (all_output_paths, player_lists) = {
             let state = state_clone.lock().unwrap();
-            (state.all_output_paths.clone(), state.player_list.clone())
+            (state.all_output_paths.clone(), state.player_lists.merged())
         };
         
-        // Create cheater map for quick lookup
-        let mut cheater_map: HashMap<String, String> = HashMap::new();
-        for entry in &player_list {
-            if !entry.proof.is_empty() {
-                cheater_map.insert(entry.steamid.clone(), entry.proof[0].clone());
-            }
-        }
-        
-        // Aggregate player stats and usernames
-        let mut player_aggregate: HashMap<String, (String, usize)> = HashMap::new();
-        let mut steamid_to_usernames: HashMap<String, HashSet<String>> = HashMap::new();
+        // Aggregate demo counts and name histories
+        let mut demo_counts: HashMap<String, usize> = HashMap::new();
+        let mut name_histories: HashMap<String, history::NameHistory> = HashMap::new();
+        let mut counted_demos: HashSet<PathBuf> = HashSet::new();
+        let mut demo_players: HashMap<PathBuf, Vec<String>> = HashMap::new();
         
         // Process all output files
         for output_path in &all_output_paths {
             if output_path.exists() {
                 let output = load_existing_results(output_path);
-                for (_, players) in output {
+                for (demo, players) in output {
+                    // The same demo can be in several dumps
+                    if !counted_demos.insert(demo.clone()) {
+                        continue;
+                    }
+                    let seen = history::demo_time(&demo);
+                    demo_players.insert(demo.clone(), players.values().cloned().collect());
                     for (username, steam_id) in players {
-                        // Update player aggregate
-                        let entry = player_aggregate.entry(steam_id.clone())
-                            .or_insert((username.clone(), 0));
-                        entry.1 += 1;
-                        
-                        // Update username to the most recent one
-                        entry.0 = username.clone();
-                        
-                        // Add to username mapping
-                        steamid_to_usernames
-                            .entry(steam_id.clone())
-                            .or_default()
-                            .insert(username);
+                        *demo_counts.entry(steam_id.clone()).or_default() += 1;
+                        name_histories.entry(steam_id).or_default().record(&username, &demo, seen);
                     }
                 }
             }
         }
+        for history in name_histories.values_mut() {
+            history.finish();
+        }
         
         // Convert to player stats
         let mut player_stats = Vec::new();
         let mut bad_actor_count = 0;
         
-        for (steam_id, (username, demo_count)) in player_aggregate {
-            let is_cheater = cheater_map.contains_key(&steam_id);
-            if is_cheater {
+        for (steam_id, demo_count) in demo_counts {
+            let username = name_histories.get(&steam_id)
+                .and_then(|history| history.current())
+                .unwrap_or_default()
+                .to_string();
+            let merged = player_lists.get(&steam_id);
+            if merged.is_some() {
                 bad_actor_count += 1;
             }
             
@@ -2798,8 +4085,7 @@ impl DemoParserApp {
                 username,
                 steamid: steam_id.clone(),
                 demo_count,
-                is_cheater,
-                proof: cheater_map.get(&steam_id).cloned(),
+                marks: merged.map(|merged| merged.marks.clone()).unwrap_or_default(),
             });
         }
         
@@ -2811,21 +4097,17 @@ impl DemoParserApp {
         state.player_stats = player_stats;
         state.filtered_players = state.player_stats.clone(); // Initialize filtered players
         state.bad_actor_count = bad_actor_count;
-        state.steamid_to_usernames = steamid_to_usernames;
+        state.name_histories = Arc::new(name_histories);
+        state.demo_players = Arc::new(demo_players);
     }
 
-    fn analyze_demo(&self, _path: &Path, players: &HashMap<String, String>, player_list: &[PlayerListEntry]) -> DemoAnalysis {
+    fn analyze_demo(&self, _path: &Path, players: &HashMap<String, String>, marked_steamids: &HashMap<String, playerlist::MergedPlayer>) -> DemoAnalysis {
         let total_players = players.len();
         let mut marked_players = 0;
 
-        // Create a HashSet of marked SteamIDs for faster lookup
-        let marked_steamids: HashSet<&String> = player_list.iter()
-            .map(|entry| &entry.steamid)
-            .collect();
-
         // Count marked players in the demo
         for (_, steamid) in players {
-            if marked_steamids.contains(steamid) {
+            if marked_steamids.contains_key(steamid) {
                 marked_players += 1;
             }
         }
@@ -2852,11 +4134,11 @@ impl DemoParserApp {
     }
 
     fn update_demo_analyses(&mut self) {
-        let (demo_list, player_list, output, tf2_folder) = {
+        let (demo_list, player_lists, output, tf2_folder) = {
             let state = self.state.lock().unwrap();
             (
                 state.demo_list.clone(),
-                state.player_list.clone(),
+                state.player_lists.merged(),
                 self.output.lock().unwrap().clone(),
                 state.tf2_folder.clone(),
             )
@@ -2866,7 +4148,7 @@ impl DemoParserApp {
         
         for (path, _) in &demo_list {
             if let Some(players) = output.get(path) {
-                let mut analysis = self.analyze_demo(path, players, &player_list);
+                let mut analysis = self.analyze_demo(path, players, &player_lists);
 
                 // Check for replay if TF2 folder is set
                 if let Some(tf2_folder) = &tf2_folder {
@@ -2893,8 +4175,80 @@ fn main() -> eframe::Result<()> {
     // If there are arguments, check for test commands
     if args.len() > 1 {
         match args[1].as_str() {
-            "test_oob" => {
-                test_oob_detection();
+            "generate_trace" => {
+                if args.len() < 3 {
+                    println!("Usage: {} generate_trace <output_dir> [--players N] [--ticks N] [--seed N] [--inject kind:player:tick[:duration]]...", args[0]);
+                    println!("Cheat kinds: psilent, snap, antiaim, oob, smoothing");
+                    return Ok(());
+                }
+                match cheater_detection::tracegen::TraceConfig::from_args(&args[3..]) {
+                    Ok(config) => {
+                        let trace = cheater_detection::tracegen::generate(&config);
+                        match cheater_detection::tracegen::write_fixture(&trace, Path::new(&args[2])) {
+                            Ok(()) => println!("Wrote {} rows and {} labels to {}", trace.rows.len(), trace.labels.expected.len(), args[2]),
+                            Err(e) => println!("Error writing trace: {}", e),
+                        }
+                    }
+                    Err(e) => println!("Error: {}", e),
+                }
+                return Ok(());
+            },
+            "test_corpus" => {
+                // Replay the labelled fixtures and report precision/recall per algorithm
+                let dir = args.get(2).map(PathBuf::from)
+                    .unwrap_or_else(|| PathBuf::from(cheater_detection::corpus::CORPUS_DIR));
+                match cheater_detection::corpus::run_corpus(&dir) {
+                    Ok(reports) => cheater_detection::corpus::print_report(&reports),
+                    Err(e) => println!("Error running corpus: {}", e),
+                }
+                return Ok(());
+            },
+            "report" => {
+                // Check the demo and write the evidence report for one player
+                if args.len() < 4 {
+                    println!("Usage: {} report <path_to_demo> <steamid64>", args[0]);
+                    return Ok(());
+                }
+                let demo_path = Path::new(&args[2]);
+                let steamid = match args[3].parse::<u64>() {
+                    Ok(steamid) => steamid,
+                    Err(_) => {
+                        println!("Invalid steamid64: {}", args[3]);
+                        return Ok(());
+                    }
+                };
+                let settings = AppSettings::load();
+                match analyze_demo_for_cheaters(demo_path, settings.tf2_folder.as_deref(), &|_| {}, &|message| println!("{}", message)) {
+                    Ok((detections, viewangles_file, _)) => {
+                        let input = report::ReportInput {
+                            demo_path,
+                            steamid,
+                            viewangles_file: viewangles_file.as_deref(),
+                            known_names: Vec::new(),
+                        };
+                        match report::write_report(&input, &detections) {
+                            Ok(path) => println!("Report written to {}", path.display()),
+                            Err(e) => println!("Error: {}", e),
+                        }
+                    }
+                    Err(e) => println!("Error: {}", e),
+                }
+                return Ok(());
+            },
+            "convert_list" => {
+                // Rewrite a player list in the TF2BD schema with Steam32 ids
+                if args.len() < 4 {
+                    println!("Usage: {} convert_list <input.json> <output.json>", args[0]);
+                    return Ok(());
+                }
+                let result = fs::read_to_string(&args[2])
+                    .map_err(|e| format!("Failed to read {}: {}", args[2], e))
+                    .and_then(|text| playerlist::parse_list(&text))
+                    .and_then(|list| playerlist::write_list(&list, Path::new(&args[3]), playerlist::ListFormat::Tf2bd).map(|_| list.players.len()));
+                match result {
+                    Ok(count) => println!("Wrote {} players to {}", count, args[3]),
+                    Err(e) => println!("Error: {}", e),
+                }
                 return Ok(());
             },
             "analyze_oob_pitch" => {
@@ -3315,7 +4669,9 @@ fn find_steam_tf2_folder() -> Option<PathBuf> {
 }
 
 // Demo checker function
-fn analyze_demo_for_cheaters(path: &Path) -> Result<(Vec<Detection>, Option<PathBuf>), String> {
+/// `progress` is called with the share of the demo's ticks analysed, each time it grows by a percent,
+/// `log` with status lines worth showing the user
+fn analyze_demo_for_cheaters(path: &Path, tf2_folder: Option<&Path>, progress: &dyn Fn(f32), log: &dyn Fn(String)) -> Result<(Vec<Detection>, Option<PathBuf>, ViewAngleRecording), String> {
     // Read file contents first
     let bytes = match fs::read(path) {
         Ok(bytes) => bytes,
@@ -3333,112 +4689,50 @@ fn analyze_demo_for_cheaters(path: &Path) -> Result<(Vec<Detection>, Option<Path
     // Create our view angles extractor with the output file path
     let mut view_angles_extractor = ViewAnglesToCSV::new(output_file.clone());
     
-    // Use a more direct approach to parse the demo
-    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        // Create a demo object and parser
-        let demo = Demo::new(&bytes);
-        let parser = DemoParser::new(demo.get_stream());
+    // Statistical algorithms look at the whole demo, so they get every tick as well
+    let mut analyser = CheatAnalyser::new(all_algorithms());
+    if let Err(e) = analyser.init() {
+        return Err(format!("Failed to initialise cheat algorithms: {}", e));
+    }
+    
+    // Replay the demo packet by packet so the algorithms see the real game state every tick
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| -> Result<(Vec<Detection>, Option<PathBuf>, ViewAngleRecording), String> {
+        // Only recorded for the timeline plot, the CSV extractor reports the flicks
+        let mut angle_recorder = ViewAnglesAnalyzer::new();
+        let mut all_detections = Vec::new();
         
-        // Parse the demo to get player information
-        match parser.parse() {
-            Ok((_, parser_result)) => {
-                println!("Successfully parsed demo file");
-                
-                // Extract player information from parsing result
-                let mut player_states = HashMap::new();
-                let mut all_detections = Vec::new();
-                
-                // Store player names
-                for (user_id, user) in &parser_result.users {
-                    // Convert UserId to u64 - TF2 demo parser uses string representation
-                    let user_steamid = user_id.to_string().parse::<u64>()
-                        .unwrap_or_else(|_| rand::thread_rng().gen_range(100000..999999));
-                    let user_name = user.name.clone();
-                    
-                    // Create state for each player with their name
-                    player_states.insert(user_steamid, cheater_detection::PlayerState {
-                        steamid: user_steamid,
-                        viewangles: None, 
-                        position: None,
-                        name: user_name.clone(),
-                    });
-                    
-                    println!("Added player: {} with ID {}", user_name, user_steamid);
-                }
-                
-                // Process all state and command information
-                let mut current_tick = 0;
-                
-                // Instead of trying to access entities directly, which might not be available,
-                // we'll generate view angles that change over time but are more realistic
-                
-                // Use a reasonable number of ticks for the simulation
-                const TOTAL_TICKS: u32 = 1000;
-                
-                println!("Processing {} ticks for {} players", TOTAL_TICKS, player_states.len());
-                
-                // Process each tick
-                for tick in 0..TOTAL_TICKS {
-                    current_tick = tick;
-                    
-                    // For each player, update their view angles
-                    for (player_id, player_state) in &mut player_states {
-                        // Generate semi-realistic viewangles that change over time
-                        let player_seed = (*player_id % 100) as f32 * 0.1;
-                        
-                        // Base angles with smooth sine/cosine movement
-                        let base_pitch = ((tick as f32 / (TOTAL_TICKS as f32 * 0.1)).sin() * 45.0) + 45.0;
-                        let base_yaw = ((tick as f32 / (TOTAL_TICKS as f32 * 0.05)).cos() * 180.0) + 180.0;
-                        
-                        // Add player-specific variation
-                        let pitch = base_pitch + player_seed * 10.0;
-                        let yaw = base_yaw + player_seed * 20.0;
-                        
-                        // Add small random noise for realism
-                        let noise_pitch = rand::thread_rng().gen_range(-0.5..0.5);
-                        let noise_yaw = rand::thread_rng().gen_range(-1.0..1.0);
-                        
-                        // Set the viewangles in the player state
-                        player_state.viewangles = Some((pitch + noise_pitch, yaw + noise_yaw, 0.0));
-                        
-                        // Add position data that changes over time
-                        player_state.position = Some((
-                            100.0 * (tick as f32 / TOTAL_TICKS as f32).sin() + player_seed * 50.0,
-                            100.0 * (tick as f32 / TOTAL_TICKS as f32).cos() + player_seed * 30.0,
-                            50.0 + (tick as f32 / 10.0).sin() * 10.0 // Height varies slightly
-                        ));
-                        
-                        // Every 100 ticks, add a suspicious flick for some players to test detection
-                        if *player_id % 5 == 0 && tick % 100 == 0 && tick > 0 {
-                            // Apply a sudden large movement to simulate an aimbot flick
-                            player_state.viewangles = Some((
-                                pitch + 40.0, // Large pitch change
-                                yaw + 120.0,  // Large yaw change
-                                0.0
-                            ));
-                        }
-                    }
-                    
-                    // Create temporary state for algorithm
-                    let state = cheater_detection::CheatAnalyserState {
-                        tick: current_tick,
-                        player_states: player_states.clone(),
-                    };
-                    
-                    // Process this tick with our algorithm
-                    let tick_detections = view_angles_extractor.process_tick(current_tick, &state.player_states);
-                    all_detections.extend(tick_detections);
-                }
-                
-                println!("Processed {} ticks with {} players", current_tick, player_states.len());
-                
-                // Finish and save the output file
-                let output_path = view_angles_extractor.finish();
-                
-                Ok((all_detections, Some(output_path)))
-            },
-            Err(e) => Err(format!("Failed to parse demo: {}", e)),
+        let map_dirs = cheater_detection::bsp::map_search_dirs(path, tf2_folder);
+        cheater_detection::demo::replay_demo(&bytes, path, &map_dirs, &mut analyser, &mut |tick, player_states, analyser, parser_state| {
+            all_detections.extend(view_angles_extractor.process_tick(tick, player_states));
+            
+            let recorder_state = CheatAnalyserState {
+                tick,
+                demo_kind: analyser.get_demo_kind(),
+                tick_interval: analyser.get_tick_interval(),
+                player_states: player_states.clone(),
+                hit_events: Vec::new(),
+                map: None,
+                usercmd_players: analyser.get_usercmd_players().clone(),
+            };
+            let _ = angle_recorder.on_tick(&recorder_state, parser_state);
+        }, progress, log)?;
+        
+        if let Err(e) = analyser.finish() {
+            return Err(format!("Failed to finish cheat analysis: {}", e));
         }
+        all_detections.extend(analyser.get_detections().iter().cloned());
+        
+        // The CSV extractor's detections don't go through the analyser
+        for detection in &mut all_detections {
+            if detection.timestamp.is_none() {
+                detection.timestamp = analyser.timestamp_for(detection.tick);
+            }
+        }
+        
+        // Finish and save the output file
+        let output_path = view_angles_extractor.finish();
+        
+        Ok((all_detections, Some(output_path), angle_recorder.take_recording()))
     })) {
         Ok(result) => result,
         Err(e) => {
@@ -3454,6 +4748,27 @@ fn analyze_demo_for_cheaters(path: &Path) -> Result<(Vec<Detection>, Option<Path
     }
 }
 
+/// Flagged players of a checked demo as (steamid, name, detection count), most detections first
+fn flagged_players(detections: &[Detection]) -> Vec<(u64, String, usize)> {
+    let mut players: HashMap<u64, (String, usize)> = HashMap::new();
+    for detection in detections {
+        let entry = players.entry(detection.player).or_insert_with(|| (String::new(), 0));
+        entry.1 += 1;
+        if let Some(name) = detection.data.get("player_name").and_then(|n| n.as_str()).filter(|n| !n.is_empty()) {
+            entry.0 = name.to_string();
+        }
+    }
+    
+    let mut flagged: Vec<(u64, String, usize)> = players.into_iter()
+        .map(|(steamid, (name, count))| {
+            let name = if name.is_empty() { steamid.to_string() } else { name };
+            (steamid, name, count)
+        })
+        .collect();
+    flagged.sort_by(|a, b| b.2.cmp(&a.2));
+    flagged
+}
+
 // Function to open a file with the default system application
 fn open_file(path: &Path) -> Result<(), String> {
     #[cfg(target_os = "windows")]
@@ -4257,39 +5572,3 @@ fn analyze_and_display_oob_pitch(path: &Path, threshold: f32) -> Result<(), Stri
     
     Ok(())
 }
-
-fn test_oob_detection() {
-    // Test code
-    println!("Testing OOB pitch detection...");
-    
-    let path = std::path::Path::new("test_oob_pitch.csv");
-    if !path.exists() {
-        println!("Test file not found: {}", path.display());
-        return;
-    }
-    
-    match analyze_oob_pitch(path, 89.8) {
-        Ok(detections) => {
-            println!("Found {} OOB pitch violations", detections.len());
-            
-            // Print the first 10 detections
-            for (i, (id, name, tick, pitch)) in detections.iter().take(10).enumerate() {
-                println!("{}. Player {} (ID: {}) at tick {}: pitch = {:.2}°", 
-                    i+1, name, id, tick, pitch);
-            }
-            
-            if detections.len() > 10 {
-                println!("... and {} more", detections.len() - 10);
-            }
-            
-            // Now test the full display function
-            match analyze_and_display_oob_pitch(path, 89.8) {
-                Ok(_) => println!("Analysis and display completed successfully"),
-                Err(e) => println!("Error during analysis and display: {}", e),
-            }
-        },
-        Err(e) => {
-            println!("Error during OOB pitch analysis: {}", e);
-        }
-    }
-}
\ No newline at end of file
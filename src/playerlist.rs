@@ -0,0 +1,892 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use eframe::egui;
+
+use crate::normalize_steamid;
+
+pub const DEFAULT_LIST_URL: &str = "https://github.com/AveraFox/Tom/blob/main/playerlist.vorobey-hackerpolice.json";
+// Our own marks, next to dd_settings.cfg
+pub const LOCAL_LIST_FILE: &str = "dd_playerlist.json";
+const LOCAL_LIST_NAME: &str = "Local";
+const LOCAL_LIST_COLOR: [u8; 3] = [255, 200, 60];
+// Last downloaded copy of each list, so marks are there offline and before the refresh finishes
+pub const LIST_CACHE_DIR: &str = "list_cache";
+pub const TF2BD_SCHEMA: &str = "https://raw.githubusercontent.com/PazerOP/tf2_bot_detector/master/schemas/v3/playerlist.schema.json";
+
+/// How much a list is believed when it disagrees with others
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrustLevel {
+    Low,
+    Normal,
+    High,
+}
+
+impl TrustLevel {
+    pub const ALL: [TrustLevel; 3] = [TrustLevel::Low, TrustLevel::Normal, TrustLevel::High];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrustLevel::Low => "Low",
+            TrustLevel::Normal => "Normal",
+            TrustLevel::High => "High",
+        }
+    }
+}
+
+/// A subscribed player list, saved in the settings
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListSource {
+    pub name: String,
+    pub url: String,
+    pub color: [u8; 3],
+    pub trust: TrustLevel,
+    pub enabled: bool,
+    // `url` resolved, None until `resolve` runs after loading or editing the url
+    #[serde(skip)]
+    location: Option<Result<ListLocation, String>>,
+}
+
+impl ListSource {
+    pub fn new(name: &str, url: &str) -> Self {
+        let mut source = Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            color: [220, 80, 60],
+            trust: TrustLevel::Normal,
+            enabled: true,
+            location: None,
+        };
+        source.resolve();
+        source
+    }
+
+    /// Resolve `url` again, call whenever it changes. Paths are checked on disk so
+    /// this isn't done every frame
+    pub fn resolve(&mut self) {
+        self.location = Some(resolve_source(&self.url));
+    }
+
+    /// Why `url` can't be used, as of the last `resolve`
+    pub fn location_error(&self) -> Option<&str> {
+        match &self.location {
+            Some(Err(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Attributes we act on, anything else a list uses (racist, ...) is ignored
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PlayerAttribute {
+    Cheater,
+    Suspicious,
+    Bot,
+    Exploiter,
+}
+
+impl PlayerAttribute {
+    pub const ALL: [PlayerAttribute; 4] = [PlayerAttribute::Cheater, PlayerAttribute::Suspicious, PlayerAttribute::Bot, PlayerAttribute::Exploiter];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cheater" => Some(PlayerAttribute::Cheater),
+            "suspicious" => Some(PlayerAttribute::Suspicious),
+            "bot" => Some(PlayerAttribute::Bot),
+            "exploiter" => Some(PlayerAttribute::Exploiter),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlayerAttribute::Cheater => "cheater",
+            PlayerAttribute::Suspicious => "suspicious",
+            PlayerAttribute::Bot => "bot",
+            PlayerAttribute::Exploiter => "exploiter",
+        }
+    }
+}
+
+/// Player list in the TF2 Bot Detector schema, also used by MegaAntiCheat.
+/// Fields we don't use are kept in `extra` so lists survive a round trip.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PlayerListFile {
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_info: Option<FileInfo>,
+    pub players: Vec<PlayerListEntry>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FileInfo {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerListEntry {
+    #[serde(deserialize_with = "deserialize_steamid")]
+    pub steamid: String, // Steam32, [U:1:x]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proof: Vec<String>,
+    #[serde(default)]
+    pub attributes: Vec<String>, // kept as written, see `PlayerAttribute` for the ones we use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<LastSeen>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl PlayerListEntry {
+    pub fn new(steamid: &str) -> Self {
+        Self {
+            steamid: steamid.to_string(),
+            proof: Vec::new(),
+            attributes: Vec::new(),
+            last_seen: None,
+            note: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastSeen {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>, // unix seconds
+}
+
+/// SteamIDs come as Steam64 numbers, Steam64 strings or [U:1:x], stored as Steam32
+fn deserialize_steamid<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let raw = match Value::deserialize(deserializer)? {
+        Value::String(steamid) => steamid.trim().to_string(),
+        Value::Number(steamid) => steamid.to_string(),
+        other => return Err(serde::de::Error::custom(format!("invalid steamid {}", other))),
+    };
+    Ok(normalize_steamid(&raw).map(|(id32, _)| id32).unwrap_or(raw))
+}
+
+/// Parse a player list, either a TF2BD file or the older `{players: [...]}` files
+pub fn parse_list(text: &str) -> Result<PlayerListFile, String> {
+    serde_json::from_str(text).map_err(|e| format!("Failed to parse player list: {}", e))
+}
+
+/// Schemas a list can be written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    Tf2bd, // TF2 Bot Detector / MegaAntiCheat
+    Plain, // just {players: [{steamid, proof, attributes}]}
+}
+
+impl ListFormat {
+    pub const ALL: [ListFormat; 2] = [ListFormat::Tf2bd, ListFormat::Plain];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ListFormat::Tf2bd => "TF2BD / MAC",
+            ListFormat::Plain => "Plain",
+        }
+    }
+}
+
+/// Write a list so other tools can load it
+pub fn write_list(list: &PlayerListFile, path: &Path, format: ListFormat) -> Result<(), String> {
+    let mut list = list.clone();
+    match format {
+        ListFormat::Tf2bd => {
+            if list.schema.is_none() {
+                list.schema = Some(TF2BD_SCHEMA.to_string());
+            }
+        }
+        ListFormat::Plain => {
+            list.schema = None;
+            list.file_info = None;
+            list.extra.clear();
+            for entry in &mut list.players {
+                entry.last_seen = None;
+                entry.note = None;
+                entry.extra.clear();
+            }
+        }
+    }
+    let contents = serde_json::to_string_pretty(&list).map_err(|e| format!("Failed to serialize player list: {}", e))?;
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// What one list says about a player
+#[derive(Clone, Debug)]
+pub struct SourceMark {
+    pub source: String,
+    pub color: [u8; 3],
+    pub trust: TrustLevel,
+    pub attributes: Vec<PlayerAttribute>,
+    pub proof: Vec<String>,
+    pub note: Option<String>,
+}
+
+/// Everything the enabled lists say about one player, most trusted list first
+#[derive(Clone, Debug, Default)]
+pub struct MergedPlayer {
+    pub marks: Vec<SourceMark>,
+}
+
+/// All subscribed lists and their merged view by SteamID
+#[derive(Clone, Default)]
+pub struct PlayerLists {
+    pub sources: Vec<ListSource>,
+    entries: HashMap<String, Vec<PlayerListEntry>>, // loaded entries by source url
+    updated: HashMap<String, u64>, // when each source was last fetched or confirmed unchanged
+    errors: HashMap<String, String>, // why the last fetch of a source failed
+    local: PlayerListFile, // editable list saved to LOCAL_LIST_FILE
+    merged: Arc<HashMap<String, MergedPlayer>>,
+}
+
+impl PlayerLists {
+    pub fn new(mut sources: Vec<ListSource>) -> Self {
+        for source in &mut sources {
+            source.resolve();
+        }
+        let mut lists = Self {
+            sources,
+            ..Default::default()
+        };
+        // A broken local list is left alone on disk rather than overwritten on the next mark
+        if let Ok(text) = std::fs::read_to_string(LOCAL_LIST_FILE) {
+            match parse_list(&text) {
+                Ok(local) => lists.local = local,
+                Err(e) => println!("{}: {}", LOCAL_LIST_FILE, e),
+            }
+        }
+        lists.merge();
+        lists
+    }
+
+    pub fn local_entry(&self, steamid: &str) -> Option<&PlayerListEntry> {
+        self.local.players.iter().find(|entry| entry.steamid == steamid)
+    }
+
+    /// Add or replace our own mark of a player and save the local list
+    pub fn mark_local(&mut self, mut entry: PlayerListEntry) -> Result<(), String> {
+        if let Some(existing) = self.local.players.iter().find(|existing| existing.steamid == entry.steamid) {
+            // Keep what other tools wrote to the entry
+            entry.extra = existing.extra.clone();
+        }
+        self.local.players.retain(|existing| existing.steamid != entry.steamid);
+        self.local.players.push(entry);
+        self.save_local()
+    }
+
+    pub fn unmark_local(&mut self, steamid: &str) -> Result<(), String> {
+        self.local.players.retain(|entry| entry.steamid != steamid);
+        self.save_local()
+    }
+
+    fn save_local(&mut self) -> Result<(), String> {
+        if self.local.file_info.is_none() {
+            self.local.file_info = Some(FileInfo {
+                title: Some("demo-dumper local list".to_string()),
+                ..Default::default()
+            });
+        }
+        self.merge();
+        write_list(&self.local, Path::new(LOCAL_LIST_FILE), ListFormat::Tf2bd)
+    }
+
+    pub fn set_list(&mut self, list: CachedList) {
+        self.errors.remove(&list.url);
+        self.updated.insert(list.url.clone(), list.updated);
+        self.entries.insert(list.url, list.players);
+        self.merge();
+    }
+
+    pub fn set_error(&mut self, url: &str, error: String) {
+        self.errors.insert(url.to_string(), error);
+    }
+
+    /// Problem with a source, either its URL or its last fetch
+    pub fn error(&self, source: &ListSource) -> Option<String> {
+        source.location_error().map(str::to_string).or_else(|| self.errors.get(&source.url).cloned())
+    }
+
+    pub fn last_updated(&self, url: &str) -> Option<u64> {
+        self.updated.get(url).copied()
+    }
+
+    /// Update time of the stalest enabled list, None until one has loaded
+    pub fn oldest_update(&self) -> Option<u64> {
+        self.sources.iter()
+            .filter(|source| source.enabled)
+            .filter_map(|source| self.last_updated(&source.url))
+            .min()
+    }
+
+    pub fn entry_count(&self, url: &str) -> Option<usize> {
+        self.entries.get(url).map(Vec::len)
+    }
+
+    /// Rebuild the merged view, call after sources are edited
+    pub fn merge(&mut self) {
+        // Stable sort keeps list order between lists of the same trust
+        let mut sources: Vec<&ListSource> = self.sources.iter().filter(|source| source.enabled).collect();
+        sources.sort_by(|a, b| b.trust.cmp(&a.trust));
+
+        // Our own marks always come first
+        let local = ListSource {
+            name: LOCAL_LIST_NAME.to_string(),
+            url: String::new(),
+            color: LOCAL_LIST_COLOR,
+            trust: TrustLevel::High,
+            enabled: true,
+            location: None,
+        };
+        let lists = std::iter::once((&local, self.local.players.as_slice()))
+            .chain(sources.into_iter().map(|source| {
+                (source, self.entries.get(&source.url).map(Vec::as_slice).unwrap_or(&[]))
+            }));
+
+        let mut merged: HashMap<String, MergedPlayer> = HashMap::new();
+        for (source, entries) in lists {
+            for entry in entries {
+                let mut attributes: Vec<PlayerAttribute> = entry.attributes.iter()
+                    .filter_map(|attribute| PlayerAttribute::parse(attribute))
+                    .collect();
+                attributes.sort();
+                attributes.dedup();
+                if attributes.is_empty() {
+                    continue;
+                }
+                merged.entry(entry.steamid.clone()).or_default().marks.push(SourceMark {
+                    source: source.name.clone(),
+                    color: source.color,
+                    trust: source.trust,
+                    attributes,
+                    proof: entry.proof.clone(),
+                    note: entry.note.clone(),
+                });
+            }
+        }
+        self.merged = Arc::new(merged);
+    }
+
+    /// Merged players, cheap to clone for the UI
+    pub fn merged(&self) -> Arc<HashMap<String, MergedPlayer>> {
+        self.merged.clone()
+    }
+}
+
+/// Last good copy of a list, with what the server needs to tell us it hasn't changed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedList {
+    pub url: String,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    pub updated: u64, // unix seconds of the last successful check
+    pub players: Vec<PlayerListEntry>,
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// "5m ago" style age of a unix timestamp
+pub fn format_age(timestamp: u64) -> String {
+    let seconds = unix_now().saturating_sub(timestamp);
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+/// FNV-1a of the url, stable across builds so the cache survives updates
+fn cache_path(url: &str) -> PathBuf {
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    Path::new(LIST_CACHE_DIR).join(format!("{:016x}.json", hash))
+}
+
+/// Cached copy of a list from an earlier run, if any
+pub fn load_cached(url: &str) -> Option<CachedList> {
+    let text = std::fs::read_to_string(cache_path(url)).ok()?;
+    serde_json::from_str::<CachedList>(&text).ok().filter(|cached| cached.url == url)
+}
+
+fn save_cached(cached: &CachedList) -> Result<(), String> {
+    std::fs::create_dir_all(LIST_CACHE_DIR).map_err(|e| format!("Failed to create {}: {}", LIST_CACHE_DIR, e))?;
+    let contents = serde_json::to_string(cached).map_err(|e| format!("Failed to serialize player list cache: {}", e))?;
+    std::fs::write(cache_path(&cached.url), contents).map_err(|e| format!("Failed to write player list cache: {}", e))
+}
+
+/// Where a list source is read from once its URL is resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListLocation {
+    Http(String), // raw JSON, not the forge's web page
+    File(PathBuf),
+}
+
+/// Turn what the user pasted into something that serves the raw list: forge
+/// web pages (GitHub, Gist, GitLab, Gitea/Forgejo/Codeberg, Bitbucket) are
+/// pointed at their raw file, other http(s) URLs are used as is
+pub fn resolve_source(source: &str) -> Result<ListLocation, String> {
+    let source = source.trim();
+    if source.is_empty() {
+        return Err("No URL or path given".to_string());
+    }
+
+    if !source.contains("://") || source.starts_with("file://") {
+        let path = local_path(source);
+        return if path.is_file() {
+            Ok(ListLocation::File(path))
+        } else {
+            Err(format!("{} is not a file", path.display()))
+        };
+    }
+
+    let mut url = reqwest::Url::parse(source).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme {}, use http(s) or file://", url.scheme()));
+    }
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let segments: Vec<String> = url.path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    if let Some((raw_host, raw_segments)) = raw_file(&host, &segments)? {
+        url.set_host(Some(&raw_host)).map_err(|e| format!("Invalid URL: {}", e))?;
+        url.set_path(&raw_segments.join("/"));
+        url.set_fragment(None);
+    }
+    Ok(ListLocation::Http(url.to_string()))
+}
+
+/// Path behind a plain path or a file:// URL
+fn local_path(source: &str) -> PathBuf {
+    let path = match source.strip_prefix("file://") {
+        // file:///C:/lists/x.json on Windows
+        Some(path) => path.strip_prefix('/').filter(|rest| rest.get(1..2) == Some(":")).unwrap_or(path),
+        None => source,
+    };
+    PathBuf::from(path)
+}
+
+/// Host and path of the raw file behind a forge web page, None when the URL is already raw
+fn raw_file(host: &str, segments: &[String]) -> Result<Option<(String, Vec<String>)>, String> {
+    let parts: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let joined = |host: &str, parts: &[&str]| Some((host.to_string(), parts.iter().map(|part| part.to_string()).collect()));
+
+    // GitLab, also self-hosted: /group/project/-/blob/ref/path
+    if let Some(index) = parts.windows(2).position(|pair| pair == ["-", "blob"]) {
+        let mut raw = parts.clone();
+        raw[index + 1] = "raw";
+        return Ok(joined(host, &raw));
+    }
+
+    Ok(match (host, parts.as_slice()) {
+        ("github.com", [owner, repo, "blob" | "raw", rest @ ..]) if rest.len() >= 2 => {
+            joined("raw.githubusercontent.com", &[&[*owner, *repo][..], rest].concat())
+        }
+        ("github.com", [_] | [_, _]) => return Err("Link to the list file in the repository, not the repository itself".to_string()),
+        ("gist.github.com", [user, id]) => joined("gist.githubusercontent.com", &[*user, *id, "raw"]),
+        ("gist.github.com", [user, id, "raw", rest @ ..]) => joined("gist.githubusercontent.com", &[&[*user, *id, "raw"][..], rest].concat()),
+        ("bitbucket.org", [owner, repo, "src", rest @ ..]) if rest.len() >= 2 => {
+            joined(host, &[&[*owner, *repo, "raw"][..], rest].concat())
+        }
+        // Gitea, Forgejo and Codeberg: /owner/repo/src/branch/ref/path
+        (_, [owner, repo, "src", kind @ ("branch" | "tag" | "commit"), rest @ ..]) if !rest.is_empty() => {
+            joined(host, &[&[*owner, *repo, "raw", *kind][..], rest].concat())
+        }
+        _ => None,
+    })
+}
+
+/// Fetch a list, asking the server only for changes since `cached`.
+/// Returns the cached list with a fresh `updated` when nothing changed.
+pub fn fetch(url: &str, cached: Option<&CachedList>) -> Result<CachedList, String> {
+    let raw_url = match resolve_source(url)? {
+        ListLocation::Http(raw_url) => raw_url,
+        ListLocation::File(path) => {
+            let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            return Ok(CachedList {
+                url: url.to_string(),
+                etag: None,
+                last_modified: None,
+                updated: unix_now(),
+                players: parse_list(&text)?.players,
+            });
+        }
+    };
+
+    let mut request = reqwest::blocking::Client::new().get(&raw_url);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().map_err(|e| format!("Failed to download player list: {}", e))?;
+
+    let fetched = match (response.status(), cached) {
+        (reqwest::StatusCode::NOT_MODIFIED, Some(cached)) => CachedList {
+            updated: unix_now(),
+            ..cached.clone()
+        },
+        (status, _) if status.is_success() => {
+            let header = |name: reqwest::header::HeaderName| {
+                response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+            };
+            let (etag, last_modified) = (header(reqwest::header::ETAG), header(reqwest::header::LAST_MODIFIED));
+            let text = response.text().map_err(|e| format!("Failed to download player list: {}", e))?;
+            CachedList {
+                url: url.to_string(),
+                etag,
+                last_modified,
+                updated: unix_now(),
+                players: parse_list(&text)?.players,
+            }
+        }
+        (status, _) => return Err(format!("Failed to download player list: {}", status)),
+    };
+    // the list itself is fine, it just won't be there offline next time
+    if let Err(e) = save_cached(&fetched) {
+        println!("{}", e);
+    }
+    Ok(fetched)
+}
+
+/// Small coloured label with a list's attributes, hover shows the list and its proof
+pub fn mark_badge(ui: &mut egui::Ui, mark: &SourceMark) -> egui::Response {
+    let [r, g, b] = mark.color;
+    let attributes: Vec<&str> = mark.attributes.iter().map(PlayerAttribute::label).collect();
+    let text = egui::RichText::new(format!("⚠ {}", attributes.join(", ")))
+        .small()
+        .color(egui::Color32::from_rgb(r, g, b));
+    let mut hover = format!("{} ({} trust)", mark.source, mark.trust.label());
+    if let Some(note) = &mark.note {
+        hover.push('\n');
+        hover.push_str(note);
+    }
+    for proof in &mark.proof {
+        hover.push('\n');
+        hover.push_str(proof);
+    }
+    let sense = if mark.proof.is_empty() { egui::Sense::hover() } else { egui::Sense::click() };
+    ui.add(egui::Label::new(text).sense(sense)).on_hover_text(hover)
+}
+
+/// Proof pointing at a moment in one of our demos
+pub fn demo_proof(demo_path: &Path, tick: u32) -> String {
+    format!("{} tick {}", demo_path.display(), tick)
+}
+
+/// A player going into an exported list
+pub struct ExportPlayer {
+    pub steamid: String, // Steam32
+    pub name: String,
+    pub last_seen: Option<u64>, // unix seconds
+    pub proof: Vec<String>,
+}
+
+/// List of players with one attribute, ready to publish
+pub fn build_export(title: &str, players: Vec<ExportPlayer>, attribute: PlayerAttribute) -> PlayerListFile {
+    let players = players.into_iter()
+        .map(|player| {
+            let mut entry = PlayerListEntry::new(&player.steamid);
+            entry.attributes = vec![attribute.label().to_string()];
+            entry.proof = player.proof;
+            entry.last_seen = Some(LastSeen {
+                player_name: Some(player.name).filter(|name| !name.is_empty()),
+                time: player.last_seen,
+            });
+            entry
+        })
+        .collect();
+
+    PlayerListFile {
+        schema: None,
+        file_info: Some(FileInfo {
+            title: Some(title.to_string()),
+            description: Some("Exported from demo-dumper".to_string()),
+            ..Default::default()
+        }),
+        players,
+        extra: Map::new(),
+    }
+}
+
+/// Result of the mark editor, applied to the local list by the caller
+pub enum MarkAction {
+    Save(PlayerListEntry),
+    Remove(String),
+}
+
+/// Window for adding or editing our own mark of a player
+#[derive(Clone)]
+pub struct MarkEditor {
+    steamid: String,
+    name: String,
+    attributes: Vec<(PlayerAttribute, bool)>,
+    other_attributes: Vec<String>, // attributes we don't use, kept for other tools
+    note: String,
+    proof: Vec<String>,
+    new_proof: String,
+    existing: bool,
+}
+
+impl MarkEditor {
+    /// Editor for a player, `proof` is added to what the local list already has
+    pub fn new(steamid: &str, name: &str, existing: Option<&PlayerListEntry>, proof: Vec<String>) -> Self {
+        let parsed: Vec<PlayerAttribute> = existing
+            .map(|entry| entry.attributes.iter().filter_map(|attribute| PlayerAttribute::parse(attribute)).collect())
+            .unwrap_or_else(|| vec![PlayerAttribute::Cheater]);
+        let mut all_proof = existing.map(|entry| entry.proof.clone()).unwrap_or_default();
+        for link in proof {
+            if !all_proof.contains(&link) {
+                all_proof.push(link);
+            }
+        }
+
+        Self {
+            steamid: steamid.to_string(),
+            name: name.to_string(),
+            attributes: PlayerAttribute::ALL
+                .into_iter()
+                .map(|attribute| (attribute, parsed.contains(&attribute)))
+                .collect(),
+            other_attributes: existing
+                .map(|entry| entry.attributes.iter().filter(|attribute| PlayerAttribute::parse(attribute).is_none()).cloned().collect())
+                .unwrap_or_default(),
+            note: existing.and_then(|entry| entry.note.clone()).unwrap_or_default(),
+            proof: all_proof,
+            new_proof: String::new(),
+            existing: existing.is_some(),
+        }
+    }
+
+    fn entry(&self) -> PlayerListEntry {
+        let mut entry = PlayerListEntry::new(&self.steamid);
+        entry.attributes = self.attributes.iter()
+            .filter(|(_, checked)| *checked)
+            .map(|(attribute, _)| attribute.label().to_string())
+            .chain(self.other_attributes.iter().cloned())
+            .collect();
+        entry.proof = self.proof.clone();
+        entry.note = Some(self.note.trim().to_string()).filter(|note| !note.is_empty());
+        entry.last_seen = Some(LastSeen {
+            player_name: Some(self.name.clone()).filter(|name| !name.is_empty()),
+            time: Some(unix_now()),
+        });
+        entry
+    }
+
+    /// Returns the edit once saved or removed, `open` is cleared when the window should close
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) -> Option<MarkAction> {
+        let mut action = None;
+        let title = if self.name.is_empty() { self.steamid.clone() } else { format!("{} ({})", self.name, self.steamid) };
+        egui::Window::new(format!("Mark {}", title))
+            .id(egui::Id::new("mark_editor"))
+            .collapsible(false)
+            .resizable(false)
+            .open(open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for (attribute, checked) in &mut self.attributes {
+                        ui.checkbox(checked, attribute.label());
+                    }
+                });
+                if !self.other_attributes.is_empty() {
+                    ui.label(egui::RichText::new(format!("Also kept: {}", self.other_attributes.join(", "))).small().weak());
+                }
+
+                ui.label("Note:");
+                ui.add(egui::TextEdit::multiline(&mut self.note).desired_rows(2).desired_width(360.0));
+
+                ui.label("Proof:");
+                let mut remove = None;
+                for (index, link) in self.proof.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("🗑").clicked() {
+                            remove = Some(index);
+                        }
+                        ui.label(egui::RichText::new(link).small());
+                    });
+                }
+                if let Some(index) = remove {
+                    self.proof.remove(index);
+                }
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.new_proof).hint_text("Link or demo path").desired_width(300.0));
+                    if ui.button("Add").clicked() && !self.new_proof.trim().is_empty() {
+                        self.proof.push(self.new_proof.trim().to_string());
+                        self.new_proof.clear();
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let any_attribute = self.attributes.iter().any(|(_, checked)| *checked);
+                    if ui.add_enabled(any_attribute, egui::Button::new("💾 Save")).clicked() {
+                        action = Some(MarkAction::Save(self.entry()));
+                    }
+                    if self.existing && ui.button("Unmark").clicked() {
+                        action = Some(MarkAction::Remove(self.steamid.clone()));
+                    }
+                });
+            });
+        if action.is_some() {
+            *open = false;
+        }
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TF2BD_LIST: &str = r#"{
+        "$schema": "https://raw.githubusercontent.com/PazerOP/tf2_bot_detector/master/schemas/v3/playerlist.schema.json",
+        "file_info": {"authors": ["someone"], "title": "test list", "update_url": "https://example.com/list.json", "version": 3},
+        "players": [
+            {"steamid": 76561197960287930, "attributes": ["cheater"], "last_seen": {"player_name": "gaben", "time": 1700000000}, "custom": {"kept": true}},
+            {"steamid": "76561197960265729", "attributes": ["suspicious"], "proof": ["demo"]},
+            {"steamid": "[U:1:1234]", "attributes": ["racist"]}
+        ],
+        "list_extra": "also kept"
+    }"#;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("playerlist_{}_{}", std::process::id(), name))
+    }
+
+    fn resolved(source: &str) -> String {
+        match resolve_source(source) {
+            Ok(ListLocation::Http(url)) => url,
+            other => panic!("{} resolved to {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn steamids_are_stored_as_steam32() {
+        let list = parse_list(TF2BD_LIST).unwrap();
+        let steamids: Vec<&str> = list.players.iter().map(|entry| entry.steamid.as_str()).collect();
+        assert_eq!(steamids, ["[U:1:22202]", "[U:1:1]", "[U:1:1234]"]);
+        assert!(parse_list(r#"{"players": [{"steamid": true}]}"#).is_err());
+    }
+
+    #[test]
+    fn tf2bd_round_trip_keeps_extra_fields() {
+        let path = temp_file("tf2bd.json");
+        write_list(&parse_list(TF2BD_LIST).unwrap(), &path, ListFormat::Tf2bd).unwrap();
+        let list = parse_list(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(list.schema.as_deref().unwrap().contains("playerlist.schema.json"));
+        assert_eq!(list.extra.get("list_extra"), Some(&Value::from("also kept")));
+        let file_info = list.file_info.unwrap();
+        assert_eq!(file_info.title.as_deref(), Some("test list"));
+        assert_eq!(file_info.extra.get("version"), Some(&Value::from(3)));
+        let first = &list.players[0];
+        assert_eq!(first.steamid, "[U:1:22202]");
+        assert_eq!(first.extra.get("custom"), Some(&serde_json::json!({"kept": true})));
+        assert_eq!(first.last_seen.as_ref().and_then(|seen| seen.player_name.as_deref()), Some("gaben"));
+        assert_eq!(list.players[1].proof, ["demo"]);
+    }
+
+    #[test]
+    fn plain_format_drops_tf2bd_fields() {
+        let path = temp_file("plain.json");
+        write_list(&parse_list(TF2BD_LIST).unwrap(), &path, ListFormat::Plain).unwrap();
+        let list = parse_list(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(list.schema.is_none() && list.file_info.is_none() && list.extra.is_empty());
+        assert!(list.players.iter().all(|entry| entry.extra.is_empty() && entry.last_seen.is_none()));
+        assert_eq!(list.players[0].attributes, ["cheater"]);
+    }
+
+    #[test]
+    fn forge_pages_resolve_to_raw_files() {
+        let cases = [
+            ("https://github.com/owner/repo/blob/main/lists/cheaters.json", "https://raw.githubusercontent.com/owner/repo/main/lists/cheaters.json"),
+            ("https://github.com/owner/repo/raw/main/list.json", "https://raw.githubusercontent.com/owner/repo/main/list.json"),
+            ("https://raw.githubusercontent.com/owner/repo/main/list.json", "https://raw.githubusercontent.com/owner/repo/main/list.json"),
+            ("https://gist.github.com/user/abc123", "https://gist.githubusercontent.com/user/abc123/raw"),
+            ("https://gist.github.com/user/abc123/raw/list.json", "https://gist.githubusercontent.com/user/abc123/raw/list.json"),
+            ("https://gitlab.com/group/project/-/blob/main/list.json", "https://gitlab.com/group/project/-/raw/main/list.json"),
+            ("https://git.example.com/group/sub/project/-/blob/v1/list.json", "https://git.example.com/group/sub/project/-/raw/v1/list.json"),
+            ("https://bitbucket.org/owner/repo/src/main/list.json", "https://bitbucket.org/owner/repo/raw/main/list.json"),
+            ("https://codeberg.org/owner/repo/src/branch/main/list.json", "https://codeberg.org/owner/repo/raw/branch/main/list.json"),
+            ("https://gitea.example.com/owner/repo/src/tag/v2/list.json", "https://gitea.example.com/owner/repo/raw/tag/v2/list.json"),
+            ("https://example.com/lists/cheaters.json", "https://example.com/lists/cheaters.json"),
+        ];
+        for (source, raw) in cases {
+            assert_eq!(resolved(source), raw, "{}", source);
+        }
+    }
+
+    #[test]
+    fn repository_links_are_rejected() {
+        assert!(resolve_source("https://github.com/owner/repo").is_err());
+        assert!(resolve_source("https://github.com/owner").is_err());
+        assert!(resolve_source("ftp://example.com/list.json").is_err());
+        assert!(resolve_source("   ").is_err());
+    }
+
+    #[test]
+    fn local_files_and_file_urls() {
+        assert_eq!(local_path("file:///C:/lists/x.json"), PathBuf::from("C:/lists/x.json"));
+        assert_eq!(local_path("file:///home/me/x.json"), PathBuf::from("/home/me/x.json"));
+        assert_eq!(local_path("lists/x.json"), PathBuf::from("lists/x.json"));
+
+        let path = temp_file("local.json");
+        std::fs::write(&path, TF2BD_LIST).unwrap();
+        let url = format!("file://{}", path.display());
+        assert_eq!(resolve_source(&url), Ok(ListLocation::File(path.clone())));
+        assert_eq!(resolve_source(&path.display().to_string()), Ok(ListLocation::File(path.clone())));
+        let _ = std::fs::remove_file(&path);
+        assert!(resolve_source(&url).is_err());
+    }
+
+    #[test]
+    fn sources_are_resolved_when_edited() {
+        let path = temp_file("edited.json");
+        let mut source = ListSource::new("List", &path.display().to_string());
+        let lists = PlayerLists::new(vec![source.clone()]);
+        assert!(lists.error(&source).is_some());
+
+        // The file showing up isn't noticed until the source is resolved again
+        std::fs::write(&path, TF2BD_LIST).unwrap();
+        assert!(lists.error(&source).is_some());
+        source.resolve();
+        assert_eq!(lists.error(&source), None);
+        let _ = std::fs::remove_file(&path);
+
+        source.url = "https://github.com/owner/repo".to_string();
+        source.resolve();
+        assert!(lists.error(&source).is_some());
+    }
+}
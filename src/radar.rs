@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use eframe::egui;
+
+use crate::cheater_detection::{
+    viewangles::{ViewAngleRecord, ViewAngleRecording},
+    Detection,
+};
+
+// Ticks replayed either side of a detection picked from the list
+const DETECTION_WINDOW: u32 = 330;
+// A player without a record this many samples before the shown tick is dead or gone
+const MAX_RECORD_GAP: u32 = 2;
+// Length of the aim ray in world units, shortened when looking up or down
+const AIM_RAY_LENGTH: f32 = 400.0;
+const RADAR_SIZE: f32 = 420.0;
+const PLAYER_RADIUS: f32 = 4.0;
+const SPEEDS: [f32; 6] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0];
+
+const RED_COLOR: egui::Color32 = egui::Color32::from_rgb(200, 70, 60);
+const BLU_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 130, 200);
+const SUSPECT_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 220, 60);
+
+/// Top-down replay of player positions and aim of the last checked demo
+#[derive(Clone)]
+pub struct RadarPlayback {
+    recording: Arc<ViewAngleRecording>,
+    detections: Vec<Detection>,
+    suspect: Option<u64>,
+    bounds: egui::Rect, // world x/y covered by the recording
+    range: (u32, u32),  // replayed tick range
+    tick: f32,
+    playing: bool,
+    speed: f32,
+    ticks_per_second: f32,
+    record_gap: u32, // ticks a player can go without a record, scaled by the snapshot rate
+}
+
+impl RadarPlayback {
+    pub fn new(recording: Arc<ViewAngleRecording>, detections: Vec<Detection>) -> Self {
+        let mut bounds = egui::Rect::NOTHING;
+        let (mut first_tick, mut last_tick) = (u32::MAX, 0);
+        // SourceTV below 66 snapshots a second records every few ticks, the most common gap
+        // between a player's records is the sample interval
+        let mut gaps: HashMap<u32, usize> = HashMap::new();
+        for records in recording.viewangles.values() {
+            for pair in records.windows(2) {
+                *gaps.entry(pair[1].tick.saturating_sub(pair[0].tick)).or_default() += 1;
+            }
+            for record in records {
+                if let Some((x, y, _)) = record.position {
+                    bounds.extend_with(egui::pos2(x, y));
+                }
+                first_tick = first_tick.min(record.tick);
+                last_tick = last_tick.max(record.tick);
+            }
+        }
+        if first_tick > last_tick {
+            first_tick = 0;
+            last_tick = 0;
+        }
+
+        // Suspect is the player with the most detections
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for detection in &detections {
+            *counts.entry(detection.player).or_default() += 1;
+        }
+        let suspect = counts.into_iter().max_by_key(|(_, count)| *count).map(|(player, _)| player);
+        let sample_interval = gaps.into_iter()
+            .filter(|(gap, _)| *gap > 0)
+            .max_by_key(|(_, count)| *count)
+            .map_or(1, |(gap, _)| gap);
+        let ticks_per_second = 1.0 / recording.tick_interval;
+
+        let mut radar = Self {
+            recording,
+            detections,
+            suspect,
+            bounds,
+            range: (first_tick, last_tick),
+            tick: first_tick as f32,
+            playing: false,
+            speed: 1.0,
+            ticks_per_second,
+            record_gap: MAX_RECORD_GAP * sample_interval,
+        };
+        // Open on the suspect's first detection rather than the start of the demo
+        if let Some(tick) = radar.suspect_detections().first().map(|detection| detection.tick) {
+            radar.focus(tick);
+        }
+        radar
+    }
+
+    fn suspect_detections(&self) -> Vec<&Detection> {
+        let mut detections: Vec<&Detection> = self.detections.iter()
+            .filter(|detection| Some(detection.player) == self.suspect)
+            .collect();
+        detections.sort_by_key(|detection| detection.tick);
+        detections
+    }
+
+    fn player_name(&self, player: u64) -> String {
+        self.recording.player_names.get(&player).cloned().unwrap_or_else(|| player.to_string())
+    }
+
+    /// Replay the ticks around a detection, starting a second before it
+    fn focus(&mut self, tick: u32) {
+        self.range = (tick.saturating_sub(DETECTION_WINDOW), tick + DETECTION_WINDOW);
+        self.tick = tick.saturating_sub(self.ticks_per_second as u32).max(self.range.0) as f32;
+        self.playing = false;
+    }
+
+    /// Latest record of a player at or shortly before the tick
+    fn record_at(records: &[ViewAngleRecord], tick: u32, max_gap: u32) -> Option<&ViewAngleRecord> {
+        let index = records.partition_point(|record| record.tick <= tick);
+        let record = records.get(index.checked_sub(1)?)?;
+        (tick - record.tick <= max_gap).then_some(record)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        // Controls
+        let mut focus_tick = None;
+        ui.horizontal(|ui| {
+            let mut flagged: Vec<u64> = self.detections.iter().map(|detection| detection.player).collect();
+            flagged.sort_unstable();
+            flagged.dedup();
+            let suspect_label = self.suspect.map(|player| self.player_name(player)).unwrap_or_default();
+            egui::ComboBox::from_label("Suspect")
+                .selected_text(suspect_label)
+                .show_ui(ui, |ui| {
+                    for player in flagged {
+                        let name = self.player_name(player);
+                        if ui.selectable_label(self.suspect == Some(player), name).clicked() {
+                            self.suspect = Some(player);
+                        }
+                    }
+                });
+
+            let detections = self.suspect_detections();
+            egui::ComboBox::from_label("Jump to detection")
+                .selected_text(format!("{} detections", detections.len()))
+                .show_ui(ui, |ui| {
+                    for detection in &detections {
+                        let kind = detection.data.get("type").and_then(|kind| kind.as_str()).unwrap_or("");
+                        let label = match &detection.timestamp {
+                            Some(timestamp) => format!("{} {} {}", timestamp.elapsed, detection.algorithm, kind),
+                            None => format!("tick {} {} {}", detection.tick, detection.algorithm, kind),
+                        };
+                        if ui.selectable_label(false, label).clicked() {
+                            focus_tick = Some(detection.tick);
+                        }
+                    }
+                });
+        });
+        if let Some(tick) = focus_tick {
+            self.focus(tick);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(if self.playing { "⏸" } else { "▶" }).clicked() {
+                if self.tick >= self.range.1 as f32 {
+                    self.tick = self.range.0 as f32;
+                }
+                self.playing = !self.playing;
+            }
+            ui.add(egui::Slider::new(&mut self.tick, self.range.0 as f32..=self.range.1 as f32)
+                .step_by(1.0)
+                .text("tick"));
+            egui::ComboBox::from_id_source("radar_speed")
+                .selected_text(format!("{}x", self.speed))
+                .width(60.0)
+                .show_ui(ui, |ui| {
+                    for speed in SPEEDS {
+                        ui.selectable_value(&mut self.speed, speed, format!("{}x", speed));
+                    }
+                });
+            ui.label("from");
+            ui.add(egui::DragValue::new(&mut self.range.0).speed(10.0));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut self.range.1).speed(10.0));
+        });
+        self.range.1 = self.range.1.max(self.range.0 + 1);
+
+        if self.playing {
+            let dt = ui.input(|i| i.stable_dt).min(0.1);
+            self.tick += dt * self.ticks_per_second * self.speed;
+            if self.tick >= self.range.1 as f32 {
+                self.tick = self.range.1 as f32;
+                self.playing = false;
+            }
+            ui.ctx().request_repaint();
+        }
+        self.tick = self.tick.clamp(self.range.0 as f32, self.range.1 as f32);
+
+        // Map, north up with the recording's bounds fitted into a square
+        let (response, painter) = ui.allocate_painter(egui::vec2(RADAR_SIZE, RADAR_SIZE), egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        if !self.bounds.is_positive() {
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, "No positions recorded", egui::FontId::proportional(14.0), ui.visuals().weak_text_color());
+            return;
+        }
+
+        let world_size = self.bounds.width().max(self.bounds.height()) * 1.05;
+        let scale = (RADAR_SIZE - 2.0 * PLAYER_RADIUS) / world_size;
+        let center = self.bounds.center();
+        let to_screen = |x: f32, y: f32| rect.center() + egui::vec2((x - center.x) * scale, -(y - center.y) * scale);
+
+        let grid = egui::Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.2));
+        for step in 1..8 {
+            let offset = rect.width() * step as f32 / 8.0;
+            painter.vline(rect.left() + offset, rect.y_range(), grid);
+            painter.hline(rect.x_range(), rect.top() + offset, grid);
+        }
+
+        let tick = self.tick.round() as u32;
+        let font = egui::FontId::proportional(10.0);
+        let mut players: Vec<(&u64, &Vec<ViewAngleRecord>)> = self.recording.viewangles.iter().collect();
+        // Suspect last so they're drawn on top
+        players.sort_by_key(|(player, _)| Some(**player) == self.suspect);
+
+        for (player, records) in players {
+            let record = match Self::record_at(records, tick, self.record_gap) {
+                Some(record) => record,
+                None => continue,
+            };
+            let (x, y, _) = match record.position {
+                Some(position) => position,
+                None => continue,
+            };
+            let is_suspect = Some(*player) == self.suspect;
+            let color = match self.recording.player_teams.get(player) {
+                Some(2) => RED_COLOR,
+                Some(3) => BLU_COLOR,
+                _ => ui.visuals().text_color(),
+            };
+
+            let position = to_screen(x, y);
+            let (pitch, yaw) = (record.pitch.to_radians(), record.yaw.to_radians());
+            let reach = AIM_RAY_LENGTH * pitch.cos();
+            let aim = to_screen(x + reach * yaw.cos(), y + reach * yaw.sin());
+            painter.line_segment([position, aim], egui::Stroke::new(if is_suspect { 2.0 } else { 1.0 }, color.gamma_multiply(0.8)));
+
+            if is_suspect {
+                painter.circle_stroke(position, PLAYER_RADIUS + 3.0, egui::Stroke::new(2.0, SUSPECT_COLOR));
+            }
+            painter.circle_filled(position, PLAYER_RADIUS, color);
+            painter.text(position + egui::vec2(PLAYER_RADIUS + 2.0, -PLAYER_RADIUS), egui::Align2::LEFT_BOTTOM, self.player_name(*player), font.clone(), color);
+        }
+
+        // Flash the detections of the suspect as they are replayed
+        let flashing = self.suspect_detections().into_iter()
+            .filter(|detection| detection.tick <= tick && tick - detection.tick < self.ticks_per_second as u32 / 2);
+        for (line, detection) in flashing.enumerate() {
+            let kind = detection.data.get("type").and_then(|kind| kind.as_str()).unwrap_or(&detection.algorithm);
+            painter.text(
+                rect.left_top() + egui::vec2(6.0, 6.0 + line as f32 * 16.0),
+                egui::Align2::LEFT_TOP,
+                kind,
+                egui::FontId::proportional(14.0),
+                SUSPECT_COLOR,
+            );
+        }
+        painter.text(rect.right_bottom() - egui::vec2(6.0, 6.0), egui::Align2::RIGHT_BOTTOM, format!("tick {}", tick), font, ui.visuals().weak_text_color());
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::encounters::DemoInfo;
+
+/// Someone who was in the same demos as the player being looked at
+#[derive(Clone, Debug)]
+pub struct CoPlayer {
+    pub steamid: String,
+    pub together: usize, // demos both appear in
+    pub same_team: usize,
+    pub opposite_team: usize, // together minus both is demos without known teams
+}
+
+/// Players sharing demos with `steamid`, most shared demos first. `demos` are the
+/// player's own demos, team splits are only counted for demos in `infos`
+pub fn co_players(
+    steamid: &str,
+    demos: &[PathBuf],
+    demo_players: &HashMap<PathBuf, Vec<String>>,
+    infos: &HashMap<PathBuf, Result<DemoInfo, String>>,
+) -> Vec<CoPlayer> {
+    let mut found: HashMap<&str, CoPlayer> = HashMap::new();
+    for demo in demos {
+        let players = match demo_players.get(demo) {
+            Some(players) => players,
+            None => continue,
+        };
+        let info = infos.get(demo).and_then(|info| info.as_ref().ok());
+        for other in players.iter().filter(|other| *other != steamid) {
+            let co_player = found.entry(other.as_str()).or_insert_with(|| CoPlayer {
+                steamid: other.clone(),
+                together: 0,
+                same_team: 0,
+                opposite_team: 0,
+            });
+            co_player.together += 1;
+            match info.and_then(|info| info.same_team(steamid, other)) {
+                Some(true) => co_player.same_team += 1,
+                Some(false) => co_player.opposite_team += 1,
+                None => {}
+            }
+        }
+    }
+
+    let mut co_players: Vec<CoPlayer> = found.into_values().collect();
+    co_players.sort_by(|a, b| {
+        b.together.cmp(&a.together)
+            .then_with(|| b.same_team.cmp(&a.same_team))
+            .then_with(|| a.steamid.cmp(&b.steamid))
+    });
+    co_players
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tf_demo_parser::demo::parser::analyser::Team;
+
+    fn players(steamids: &[&str]) -> Vec<String> {
+        steamids.iter().map(|steamid| steamid.to_string()).collect()
+    }
+
+    #[test]
+    fn team_splits_only_count_known_teams() {
+        let demos: Vec<PathBuf> = ["a.dem", "b.dem", "c.dem", "d.dem"].iter().map(PathBuf::from).collect();
+        let mut demo_players = HashMap::new();
+        demo_players.insert(demos[0].clone(), players(&["me", "friend", "rival"]));
+        demo_players.insert(demos[1].clone(), players(&["me", "friend", "rival"]));
+        demo_players.insert(demos[2].clone(), players(&["me", "friend"]));
+        // d.dem is one of the player's demos that was never dumped
+
+        let mut infos = HashMap::new();
+        infos.insert(demos[0].clone(), Ok(DemoInfo::with_teams(None, &[("me", Team::Red), ("friend", Team::Red), ("rival", Team::Blue)])));
+        // Friend switched sides, the rival spectated
+        infos.insert(demos[1].clone(), Ok(DemoInfo::with_teams(None, &[("me", Team::Blue), ("friend", Team::Red), ("rival", Team::Spectator)])));
+        // c.dem couldn't be parsed
+        infos.insert(demos[2].clone(), Err("Demo parser panicked".to_string()));
+
+        let co_players = co_players("me", &demos, &demo_players, &infos);
+        assert_eq!(co_players.len(), 2);
+        let friend = &co_players[0];
+        assert_eq!(friend.steamid, "friend");
+        assert_eq!((friend.together, friend.same_team, friend.opposite_team), (3, 1, 1));
+        let rival = &co_players[1];
+        assert_eq!(rival.steamid, "rival");
+        assert_eq!((rival.together, rival.same_team, rival.opposite_team), (2, 0, 1));
+    }
+
+    #[test]
+    fn without_infos_only_demos_are_counted() {
+        let demos = vec![PathBuf::from("a.dem"), PathBuf::from("b.dem")];
+        let mut demo_players = HashMap::new();
+        demo_players.insert(demos[0].clone(), players(&["me", "b", "a"]));
+        demo_players.insert(demos[1].clone(), players(&["me", "b"]));
+
+        let co_players = co_players("me", &demos, &demo_players, &HashMap::new());
+        let counts: Vec<(&str, usize, usize, usize)> = co_players.iter()
+            .map(|co_player| (co_player.steamid.as_str(), co_player.together, co_player.same_team, co_player.opposite_team))
+            .collect();
+        assert_eq!(counts, [("b", 2, 0, 0), ("a", 1, 0, 0)]);
+    }
+}
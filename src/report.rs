@@ -0,0 +1,317 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cheater_detection::Detection;
+use crate::cheater_detection::math::calculate_angle_delta;
+use crate::steamid_64_to_32;
+
+// Ticks of angles shown either side of a flagged tick
+const CHART_WINDOW: u32 = 66;
+// Charts per report, the tables still list every detection
+const MAX_CHARTS: usize = 30;
+const CHART_WIDTH: f32 = 420.0;
+const CHART_HEIGHT: f32 = 140.0;
+const CHART_PADDING: f32 = 24.0;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:1000px;background:#1e1e1e;color:#ddd}\
+h1,h2,h3{color:#fff}table{border-collapse:collapse;width:100%;margin-bottom:1em}\
+td,th{border:1px solid #444;padding:4px 8px;text-align:left;vertical-align:top}th{background:#2d2d2d}\
+code{background:#2d2d2d;padding:2px 4px;user-select:all}.charts{display:flex;flex-wrap:wrap;gap:8px}\
+.chart{background:#252525;border:1px solid #444;padding:4px}.muted{color:#888}";
+
+/// One recorded angle sample of the reported player
+struct AngleSample {
+    tick: u32,
+    yaw: f32,
+    pitch: f32,
+}
+
+/// Everything the report needs besides the detections
+pub struct ReportInput<'a> {
+    pub demo_path: &'a Path,
+    pub steamid: u64,
+    /// Viewangles CSV written by the demo checker, charts are skipped without it
+    pub viewangles_file: Option<&'a Path>,
+    /// Names seen for this steamid in other demos
+    pub known_names: Vec<String>,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Read the player's angles and the names they used from the viewangles CSV
+fn load_angles(path: &Path, steamid: u64) -> Result<(Vec<AngleSample>, BTreeSet<String>), String> {
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let headers = reader.headers()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .clone();
+    let column = |name: &str| headers.iter().position(|h| h == name)
+        .ok_or_else(|| format!("{} has no {} column", path.display(), name));
+    let (tick_col, id_col, name_col) = (column("tick")?, column("player_id")?, column("player_name")?);
+    let (yaw_col, pitch_col) = (column("viewangle")?, column("pitchangle")?);
+
+    let mut samples = Vec::new();
+    let mut names = BTreeSet::new();
+    for record in reader.records().flatten() {
+        if record.get(id_col).and_then(|id| id.parse::<u64>().ok()) != Some(steamid) {
+            continue;
+        }
+        if let Some(name) = record.get(name_col).filter(|name| !name.is_empty()) {
+            names.insert(name.to_string());
+        }
+        let parsed = (
+            record.get(tick_col).and_then(|v| v.parse::<u32>().ok()),
+            record.get(yaw_col).and_then(|v| v.parse::<f32>().ok()),
+            record.get(pitch_col).and_then(|v| v.parse::<f32>().ok()),
+        );
+        if let (Some(tick), Some(yaw), Some(pitch)) = parsed {
+            samples.push(AngleSample { tick, yaw, pitch });
+        }
+    }
+
+    samples.sort_by_key(|sample| sample.tick);
+    Ok((samples, names))
+}
+
+/// Line chart of one angle around a flagged tick, as an inline SVG
+fn svg_chart(title: &str, points: &[(u32, f32)], from: u32, to: u32, flagged: u32) -> String {
+    let (mut min, mut max) = points.iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), (_, value)| (min.min(*value), max.max(*value)));
+    // Keep a still crosshair from being blown up into noise
+    if max - min < 1.0 {
+        let mid = (max + min) / 2.0;
+        min = mid - 0.5;
+        max = mid + 0.5;
+    }
+
+    let plot_width = CHART_WIDTH - 2.0 * CHART_PADDING;
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_PADDING;
+    let x = |tick: u32| CHART_PADDING + (tick - from) as f32 / (to - from).max(1) as f32 * plot_width;
+    let y = |value: f32| CHART_PADDING + (max - value) / (max - min) * plot_height;
+
+    let polyline: Vec<String> = points.iter()
+        .map(|(tick, value)| format!("{:.1},{:.1}", x(*tick), y(*value)))
+        .collect();
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg class=\"chart\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        w = CHART_WIDTH, h = CHART_HEIGHT
+    );
+    let _ = write!(svg, "<text x=\"{}\" y=\"14\" fill=\"#ddd\" font-size=\"12\">{}</text>", CHART_PADDING, escape(title));
+    let _ = write!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#444\"/>",
+        CHART_PADDING, CHART_PADDING, plot_width, plot_height
+    );
+    let _ = write!(
+        svg,
+        "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"#e04040\" stroke-dasharray=\"4 2\"/>",
+        CHART_PADDING, CHART_PADDING + plot_height, x = x(flagged)
+    );
+    let _ = write!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"#4aa3ff\" stroke-width=\"1.5\"/>", polyline.join(" "));
+    let _ = write!(svg, "<text x=\"2\" y=\"{}\" fill=\"#888\" font-size=\"10\">{:.1}</text>", CHART_PADDING + 4.0, max);
+    let _ = write!(svg, "<text x=\"2\" y=\"{}\" fill=\"#888\" font-size=\"10\">{:.1}</text>", CHART_PADDING + plot_height, min);
+    let _ = write!(svg, "<text x=\"{}\" y=\"{}\" fill=\"#888\" font-size=\"10\">{}</text>", CHART_PADDING, CHART_HEIGHT - 6.0, from);
+    let _ = write!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" fill=\"#888\" font-size=\"10\" text-anchor=\"end\">{}</text>",
+        CHART_PADDING + plot_width, CHART_HEIGHT - 6.0, to
+    );
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Yaw and pitch charts around a flagged tick, or None if no angles were recorded there
+fn angle_charts(samples: &[AngleSample], flagged: u32) -> Option<String> {
+    let from = flagged.saturating_sub(CHART_WINDOW);
+    let to = flagged + CHART_WINDOW;
+    let window: Vec<&AngleSample> = samples.iter()
+        .filter(|sample| sample.tick >= from && sample.tick <= to)
+        .collect();
+    if window.len() < 2 {
+        return None;
+    }
+
+    // Unwrap yaw so turning through +-180 doesn't draw a spike across the chart
+    let mut yaw = Vec::with_capacity(window.len());
+    let mut unwrapped = window[0].yaw;
+    let mut previous = window[0].yaw;
+    for sample in &window {
+        unwrapped += calculate_angle_delta(sample.yaw, previous);
+        previous = sample.yaw;
+        yaw.push((sample.tick, unwrapped));
+    }
+    let pitch: Vec<(u32, f32)> = window.iter().map(|sample| (sample.tick, sample.pitch)).collect();
+
+    Some(format!(
+        "<div class=\"charts\">{}{}</div>",
+        svg_chart(&format!("Yaw around tick {}", flagged), &yaw, from, to, flagged),
+        svg_chart(&format!("Pitch around tick {}", flagged), &pitch, from, to, flagged),
+    ))
+}
+
+/// Detection data without the fields that already have their own column
+fn detection_details(detection: &Detection) -> String {
+    match detection.data.as_object() {
+        Some(fields) => fields.iter()
+            .filter(|(key, _)| key.as_str() != "type" && key.as_str() != "player_name")
+            .map(|(key, value)| match value {
+                serde_json::Value::Number(number) => match number.as_f64() {
+                    Some(n) if number.is_f64() => format!("{}: {:.2}", key, n),
+                    _ => format!("{}: {}", key, number),
+                },
+                serde_json::Value::String(text) => format!("{}: {}", key, text),
+                other => format!("{}: {}", key, other),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        None => detection.data.to_string(),
+    }
+}
+
+/// Render the report for one player of a checked demo
+fn build_report(input: &ReportInput, detections: &[Detection], samples: &[AngleSample], csv_names: &BTreeSet<String>) -> String {
+    let player_detections: Vec<&Detection> = detections.iter()
+        .filter(|detection| detection.player == input.steamid)
+        .collect();
+
+    let mut demo_names: BTreeSet<String> = csv_names.clone();
+    demo_names.extend(player_detections.iter()
+        .filter_map(|detection| detection.data.get("player_name").and_then(|name| name.as_str()))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string));
+    // Name at the latest detection, that's the one people will see in game
+    let display_name = player_detections.iter()
+        .max_by_key(|detection| detection.tick)
+        .and_then(|detection| detection.data.get("player_name").and_then(|name| name.as_str()))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .or_else(|| demo_names.iter().next().cloned())
+        .unwrap_or_else(|| input.steamid.to_string());
+
+    let mut by_algorithm: BTreeMap<&str, Vec<&Detection>> = BTreeMap::new();
+    for detection in &player_detections {
+        by_algorithm.entry(detection.algorithm.as_str()).or_default().push(detection);
+    }
+
+    let demo_name = input.demo_path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let steamid32 = steamid_64_to_32(&input.steamid.to_string()).unwrap_or_default();
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Report: {}</title><style>{}</style></head><body>",
+        escape(&display_name), STYLE
+    );
+    let _ = write!(html, "<h1>{}</h1>", escape(&display_name));
+    let _ = write!(
+        html,
+        "<p><a href=\"https://steamcommunity.com/profiles/{id}\">{id}</a> &middot; {} &middot; {}</p>",
+        escape(&steamid32), escape(&demo_name), id = input.steamid
+    );
+    let _ = write!(
+        html,
+        "<p class=\"muted\">Generated {}</p>",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+
+    // Summary
+    html.push_str("<h2>Summary</h2><table>");
+    let _ = write!(html, "<tr><th>Detections</th><td>{}</td></tr>", player_detections.len());
+    for (algorithm, list) in &by_algorithm {
+        let _ = write!(html, "<tr><th>{}</th><td>{}</td></tr>", escape(algorithm), list.len());
+    }
+    if let (Some(first), Some(last)) = (
+        player_detections.iter().map(|d| d.tick).min(),
+        player_detections.iter().map(|d| d.tick).max(),
+    ) {
+        let _ = write!(html, "<tr><th>Flagged ticks</th><td>{} &ndash; {}</td></tr>", first, last);
+    }
+    let _ = write!(html, "<tr><th>Angle samples</th><td>{}</td></tr>", samples.len());
+    html.push_str("</table>");
+
+    // Detections per algorithm, with charts for the first flagged ticks
+    let mut charted = BTreeSet::new();
+    for (algorithm, list) in &by_algorithm {
+        let _ = write!(html, "<h2>{}</h2>", escape(algorithm));
+        html.push_str("<table><tr><th>Tick</th><th>Time</th><th>Type</th><th>Details</th><th>Command</th></tr>");
+        for detection in list {
+            let kind = detection.data.get("type").and_then(|kind| kind.as_str()).unwrap_or("");
+            let (elapsed, command) = match &detection.timestamp {
+                Some(timestamp) => (timestamp.elapsed.clone(), timestamp.playdemo.clone()),
+                None => (String::new(), format!("demo_gototick {}", detection.tick)),
+            };
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td></tr>",
+                detection.tick, escape(&elapsed), escape(kind), escape(&detection_details(detection)), escape(&command)
+            );
+        }
+        html.push_str("</table>");
+
+        for detection in list {
+            if charted.len() >= MAX_CHARTS || !charted.insert(detection.tick) {
+                continue;
+            }
+            if let Some(charts) = angle_charts(samples, detection.tick) {
+                html.push_str(&charts);
+            }
+        }
+    }
+    if player_detections.is_empty() {
+        html.push_str("<p>No detections for this player in this demo.</p>");
+    }
+    if samples.is_empty() {
+        html.push_str("<p class=\"muted\">No viewangle data was available, angle charts are left out.</p>");
+    }
+
+    // Name history
+    html.push_str("<h2>Name history</h2><ul>");
+    let mut all_names = demo_names.clone();
+    all_names.extend(input.known_names.iter().cloned());
+    for name in &all_names {
+        let _ = write!(
+            html,
+            "<li>{}{}</li>",
+            escape(name),
+            if demo_names.contains(name) { " <span class=\"muted\">(this demo)</span>" } else { "" }
+        );
+    }
+    html.push_str("</ul></body></html>\n");
+    html
+}
+
+/// Write the report next to the demo and return its path
+pub fn write_report(input: &ReportInput, detections: &[Detection]) -> Result<PathBuf, String> {
+    let (samples, csv_names) = match input.viewangles_file {
+        Some(path) => load_angles(path, input.steamid)?,
+        None => (Vec::new(), BTreeSet::new()),
+    };
+
+    let stem = input.demo_path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "demo".to_string());
+    let report_path = input.demo_path.with_file_name(format!("{}_{}_report.html", stem, input.steamid));
+
+    std::fs::write(&report_path, build_report(input, detections, &samples, &csv_names))
+        .map_err(|e| format!("Failed to write {}: {}", report_path.display(), e))?;
+    Ok(report_path)
+}
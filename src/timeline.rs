@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use eframe::egui;
+
+use crate::cheater_detection::{
+    viewangles::{ViewAngleRecord, ViewAngleRecording},
+    Detection,
+};
+
+// Closest zoom, in ticks across the whole plot
+const MIN_VISIBLE_TICKS: f32 = 20.0;
+const PLOT_HEIGHT: f32 = 300.0;
+// Share of the plot height used by the angles, the rest shows the deltas
+const ANGLE_LANE: f32 = 0.6;
+// Smallest delta scale, keeps jitter from filling the lane
+const MIN_DELTA_SCALE: f32 = 5.0;
+const MARKER_RADIUS: f32 = 5.0;
+
+const PITCH_COLOR: egui::Color32 = egui::Color32::from_rgb(74, 163, 255);
+const YAW_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 170, 60);
+const MARKER_COLOR: egui::Color32 = egui::Color32::from_rgb(224, 64, 64);
+
+/// Pitch/yaw/delta plot of one player of the last checked demo, with the
+/// detections drawn on top
+#[derive(Clone)]
+pub struct ViewAngleTimeline {
+    pub demo_path: PathBuf,
+    recording: Arc<ViewAngleRecording>,
+    detections: Vec<Detection>,
+    selected: Option<u64>,
+    view: (f32, f32), // visible tick range
+    show_pitch: bool,
+    show_yaw: bool,
+    show_deltas: bool,
+}
+
+impl ViewAngleTimeline {
+    pub fn new(demo_path: PathBuf, recording: Arc<ViewAngleRecording>, detections: Vec<Detection>) -> Self {
+        // Start on the player with the most detections
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for detection in &detections {
+            *counts.entry(detection.player).or_default() += 1;
+        }
+        let selected = counts.into_iter()
+            .filter(|(player, _)| recording.viewangles.contains_key(player))
+            .max_by_key(|(_, count)| *count)
+            .map(|(player, _)| player)
+            .or_else(|| recording.viewangles.keys().next().copied());
+
+        let mut timeline = Self {
+            demo_path,
+            recording,
+            detections,
+            selected,
+            view: (0.0, MIN_VISIBLE_TICKS),
+            show_pitch: true,
+            show_yaw: true,
+            show_deltas: true,
+        };
+        timeline.reset_view();
+        timeline
+    }
+
+    fn player_label(&self, player: u64) -> String {
+        let count = self.detections.iter().filter(|detection| detection.player == player).count();
+        let name = self.recording.player_names.get(&player)
+            .cloned()
+            .unwrap_or_else(|| player.to_string());
+        if count > 0 {
+            format!("{} ({} detections)", name, count)
+        } else {
+            name
+        }
+    }
+
+    fn records(&self) -> &[ViewAngleRecord] {
+        self.selected
+            .and_then(|player| self.recording.viewangles.get(&player))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn reset_view(&mut self) {
+        let records = self.records();
+        self.view = match (records.first(), records.last()) {
+            (Some(first), Some(last)) => {
+                let start = first.tick as f32;
+                (start, (last.tick as f32).max(start + MIN_VISIBLE_TICKS))
+            }
+            _ => (0.0, MIN_VISIBLE_TICKS),
+        };
+    }
+
+    /// Draw the controls and the plot, returns the gototick command of a clicked detection marker
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut players: Vec<(u64, String)> = self.recording.viewangles.keys()
+            .map(|player| (*player, self.player_label(*player)))
+            .collect();
+        players.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+
+        ui.horizontal(|ui| {
+            let selected_label = self.selected.map(|player| self.player_label(player)).unwrap_or_default();
+            let mut changed = false;
+            egui::ComboBox::from_label("Player")
+                .selected_text(selected_label)
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for (player, label) in &players {
+                        if ui.selectable_label(self.selected == Some(*player), label).clicked() {
+                            self.selected = Some(*player);
+                            changed = true;
+                        }
+                    }
+                });
+            if changed {
+                self.reset_view();
+            }
+
+            ui.checkbox(&mut self.show_pitch, egui::RichText::new("Pitch").color(PITCH_COLOR));
+            ui.checkbox(&mut self.show_yaw, egui::RichText::new("Yaw").color(YAW_COLOR));
+            ui.checkbox(&mut self.show_deltas, "Deltas");
+            if ui.button("Reset zoom").clicked() {
+                self.reset_view();
+            }
+        });
+        ui.label(egui::RichText::new("Ctrl+scroll to zoom, drag to pan, click a marker to copy its gototick command").small().weak());
+
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width(), PLOT_HEIGHT),
+            egui::Sense::click_and_drag(),
+        );
+        let rect = response.rect;
+        let painter = painter.with_clip_rect(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        // Zoom around the cursor, pan by dragging
+        let span = self.view.1 - self.view.0;
+        if let Some(pointer) = response.hover_pos() {
+            let zoom = ui.input(|i| i.zoom_delta());
+            if zoom != 1.0 {
+                let anchor = self.view.0 + (pointer.x - rect.left()) / rect.width() * span;
+                let new_span = (span / zoom).max(MIN_VISIBLE_TICKS);
+                self.view.0 = anchor - (anchor - self.view.0) / span * new_span;
+                self.view.1 = self.view.0 + new_span;
+            }
+        }
+        if response.dragged() {
+            let shift = -response.drag_delta().x / rect.width() * (self.view.1 - self.view.0);
+            self.view.0 += shift;
+            self.view.1 += shift;
+        }
+
+        let (start, end) = self.view;
+        let x = |tick: f32| rect.left() + (tick - start) / (end - start) * rect.width();
+        let angle_rect = egui::Rect::from_min_max(
+            rect.min,
+            egui::pos2(rect.right(), rect.top() + rect.height() * ANGLE_LANE),
+        );
+        let delta_rect = egui::Rect::from_min_max(egui::pos2(rect.left(), angle_rect.bottom()), rect.max);
+
+        let records = self.records();
+        let visible: Vec<&ViewAngleRecord> = records.iter()
+            .filter(|record| record.tick as f32 >= start - 1.0 && record.tick as f32 <= end + 1.0)
+            .collect();
+        // More points than pixels only costs frame time
+        let stride = (visible.len() / (rect.width() as usize * 2).max(1)).max(1);
+        let visible: Vec<&ViewAngleRecord> = visible.into_iter().step_by(stride).collect();
+
+        let grid = egui::Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.3));
+        let text_color = ui.visuals().weak_text_color();
+        let font = egui::FontId::proportional(11.0);
+
+        // Angles share one -180..180 scale, networked yaw (0..360) is moved into it
+        // and split where it wraps around. Lines are clipped to their own lane
+        let angle_painter = painter.with_clip_rect(angle_rect);
+        let angle_y = |angle: f32| angle_rect.center().y - angle / 180.0 * (angle_rect.height() / 2.0 - 4.0);
+        angle_painter.hline(angle_rect.x_range(), angle_y(0.0), grid);
+        angle_painter.text(angle_rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, "±180°", font.clone(), text_color);
+
+        if self.show_pitch {
+            let points: Vec<egui::Pos2> = visible.iter()
+                .map(|record| egui::pos2(x(record.tick as f32), angle_y(record.pitch)))
+                .collect();
+            angle_painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, PITCH_COLOR)));
+        }
+        if self.show_yaw {
+            let mut segment: Vec<egui::Pos2> = Vec::new();
+            let mut previous: Option<f32> = None;
+            for record in &visible {
+                let yaw = wrap_angle(record.yaw);
+                if previous.map_or(false, |previous| (yaw - previous).abs() > 180.0) {
+                    angle_painter.add(egui::Shape::line(std::mem::take(&mut segment), egui::Stroke::new(1.5, YAW_COLOR)));
+                }
+                segment.push(egui::pos2(x(record.tick as f32), angle_y(yaw)));
+                previous = Some(yaw);
+            }
+            angle_painter.add(egui::Shape::line(segment, egui::Stroke::new(1.5, YAW_COLOR)));
+        }
+
+        // Deltas get their own scale so small corrections stay visible
+        painter.hline(delta_rect.x_range(), delta_rect.top(), grid);
+        if self.show_deltas {
+            let scale = visible.iter()
+                .flat_map(|record| [record.va_delta, record.pa_delta])
+                .flatten()
+                .fold(MIN_DELTA_SCALE, |max, delta| max.max(delta.abs()));
+            let delta_painter = painter.with_clip_rect(delta_rect);
+            let delta_y = |delta: f32| delta_rect.center().y - delta / scale * (delta_rect.height() / 2.0 - 4.0);
+            delta_painter.hline(delta_rect.x_range(), delta_y(0.0), grid);
+            painter.text(
+                delta_rect.left_top() + egui::vec2(4.0, 2.0),
+                egui::Align2::LEFT_TOP,
+                format!("deltas ±{:.0}°", scale),
+                font.clone(),
+                text_color,
+            );
+
+            let series: [(fn(&ViewAngleRecord) -> Option<f32>, egui::Color32); 2] = [
+                (|record| record.pa_delta, PITCH_COLOR),
+                (|record| record.va_delta, YAW_COLOR),
+            ];
+            for (delta, color) in series {
+                let points: Vec<egui::Pos2> = visible.iter()
+                    .filter_map(|record| delta(record).map(|d| egui::pos2(x(record.tick as f32), delta_y(d))))
+                    .collect();
+                delta_painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+            }
+        }
+
+        painter.text(rect.left_bottom() + egui::vec2(4.0, -2.0), egui::Align2::LEFT_BOTTOM, format!("{:.0}", start), font.clone(), text_color);
+        painter.text(rect.right_bottom() + egui::vec2(-4.0, -2.0), egui::Align2::RIGHT_BOTTOM, format!("{:.0}", end), font, text_color);
+
+        // Detection markers along the top edge
+        let pointer = response.hover_pos();
+        let mut hovered: Option<&Detection> = None;
+        for detection in self.detections.iter().filter(|detection| Some(detection.player) == self.selected) {
+            let tick = detection.tick as f32;
+            if tick < start || tick > end {
+                continue;
+            }
+            let center = egui::pos2(x(tick), rect.top() + MARKER_RADIUS + 2.0);
+            let is_hovered = pointer.map_or(false, |pointer| pointer.distance(center) <= MARKER_RADIUS * 2.0);
+            painter.vline(center.x, rect.y_range(), egui::Stroke::new(1.0, MARKER_COLOR.gamma_multiply(0.4)));
+            painter.circle_filled(center, if is_hovered { MARKER_RADIUS + 2.0 } else { MARKER_RADIUS }, MARKER_COLOR);
+            if is_hovered {
+                hovered = Some(detection);
+            }
+        }
+
+        let hovered_command = hovered.map(|detection| match &detection.timestamp {
+            Some(timestamp) => timestamp.playdemo.clone(),
+            None => format!("demo_gototick {}", detection.tick),
+        });
+        let clicked = response.clicked();
+        if let (Some(detection), Some(command)) = (hovered, &hovered_command) {
+            let kind = detection.data.get("type").and_then(|kind| kind.as_str()).unwrap_or("");
+            response.on_hover_text(format!("{} {}\ntick {}\n{}", detection.algorithm, kind, detection.tick, command));
+        }
+
+        if clicked {
+            hovered_command
+        } else {
+            None
+        }
+    }
+}
+
+/// Angle in -180..180, the plot's scale
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cheater_detection::Detection;
+
+// Ticks of lead-up played at normal speed before each highlight
+const LEAD_TICKS: u32 = 330;
+// Slow motion starts this many ticks before a detection and ends this many after
+const SLOWMO_BEFORE: u32 = 66;
+const SLOWMO_AFTER: u32 = 66;
+const SLOWMO_TIMESCALE: f32 = 0.25;
+// Detections closer than this are played as one highlight instead of skipping in between
+const MERGE_TICKS: u32 = 660;
+
+/// A stretch of the demo played back in full, with the detections in it
+struct Highlight {
+    start: u32,
+    end: u32,
+    events: Vec<HighlightEvent>,
+}
+
+struct HighlightEvent {
+    tick: u32,
+    name: Option<String>, // suspect's name
+    entity: Option<u64>,  // suspect's entity index
+    algorithm: String,
+}
+
+/// One `demoactions` entry
+enum DemoAction {
+    SkipAhead { start: u32, to: u32 },
+    PlayCommands { start: u32, name: String, commands: String },
+}
+
+/// Strip characters that would end the quoted VDM value or chain a console command
+fn sanitize(text: &str) -> String {
+    text.chars().filter(|c| *c != '"' && *c != ';').collect()
+}
+
+fn highlights(detections: &[Detection]) -> Vec<Highlight> {
+    // One event per tick and player is enough to look at
+    let mut events: BTreeMap<(u32, u64), HighlightEvent> = BTreeMap::new();
+    for detection in detections {
+        events.entry((detection.tick, detection.player)).or_insert_with(|| HighlightEvent {
+            tick: detection.tick,
+            name: detection.data.get("player_name").and_then(|n| n.as_str()).map(str::to_string),
+            entity: detection.data.get("entity").and_then(|entity| entity.as_u64()),
+            algorithm: detection.algorithm.clone(),
+        });
+    }
+
+    let mut highlights: Vec<Highlight> = Vec::new();
+    for event in events.into_values() {
+        let tick = event.tick;
+        match highlights.last_mut() {
+            Some(highlight) if tick <= highlight.end + MERGE_TICKS => {
+                highlight.end = highlight.end.max(tick + SLOWMO_AFTER);
+                highlight.events.push(event);
+            }
+            _ => highlights.push(Highlight {
+                start: tick.saturating_sub(LEAD_TICKS),
+                end: tick + SLOWMO_AFTER,
+                events: vec![event],
+            }),
+        }
+    }
+    highlights
+}
+
+fn actions(highlights: &[Highlight]) -> Vec<DemoAction> {
+    let mut actions = Vec::new();
+    let mut cursor = 1;
+
+    for (index, highlight) in highlights.iter().enumerate() {
+        if highlight.start > cursor + 1 {
+            actions.push(DemoAction::SkipAhead { start: cursor, to: highlight.start });
+        }
+        cursor = highlight.end;
+
+        // Slow motion windows around the detections, overlapping ones merged
+        let slow_start = |tick: u32| tick.saturating_sub(SLOWMO_BEFORE).max(highlight.start);
+        let mut windows: Vec<(u32, u32)> = Vec::new();
+        for event in &highlight.events {
+            let (start, end) = (slow_start(event.tick), event.tick + SLOWMO_AFTER);
+            match windows.last_mut() {
+                Some(window) if start <= window.1 => window.1 = window.1.max(end),
+                _ => windows.push((start, end)),
+            }
+        }
+
+        let mut highlight_actions = Vec::new();
+        let mut spectating: Option<u64> = None;
+        for event in &highlight.events {
+            let start = slow_start(event.tick);
+            let mut commands = vec![format!(
+                "echo {} flagged {} at tick {}",
+                sanitize(&event.algorithm), sanitize(event.name.as_deref().unwrap_or("player")), event.tick
+            )];
+            // spec_player takes the entity index as well as a name, which can't have spaces here
+            if let Some(entity) = event.entity {
+                if spectating != Some(entity) {
+                    commands.push(format!("spec_player {}", entity));
+                    commands.push("spec_mode 4".to_string());
+                    spectating = Some(entity);
+                }
+            }
+            if windows.iter().any(|(window_start, _)| *window_start == start) {
+                commands.push(format!("host_timescale {}", SLOWMO_TIMESCALE));
+            }
+            highlight_actions.push(DemoAction::PlayCommands {
+                start,
+                name: format!("highlight{}_tick{}", index + 1, event.tick),
+                commands: commands.join("; "),
+            });
+        }
+        for (window, (_, end)) in windows.iter().enumerate() {
+            highlight_actions.push(DemoAction::PlayCommands {
+                start: *end,
+                name: format!("highlight{}_resume{}", index + 1, window + 1),
+                commands: "host_timescale 1".to_string(),
+            });
+        }
+
+        highlight_actions.sort_by_key(|action| match action {
+            DemoAction::SkipAhead { start, .. } | DemoAction::PlayCommands { start, .. } => *start,
+        });
+        actions.extend(highlight_actions);
+    }
+
+    actions
+}
+
+/// Build the contents of a `.vdm` that skips between the detections and plays
+/// each of them in slow motion from the suspect's view
+pub fn build_vdm(detections: &[Detection]) -> String {
+    let mut vdm = String::from("demoactions\n{\n");
+
+    for (index, action) in actions(&highlights(detections)).iter().enumerate() {
+        let _ = writeln!(vdm, "\t\"{}\"\n\t{{", index + 1);
+        match action {
+            DemoAction::SkipAhead { start, to } => {
+                let _ = writeln!(vdm, "\t\tfactory \"SkipAhead\"");
+                let _ = writeln!(vdm, "\t\tname \"skip{}\"", index + 1);
+                let _ = writeln!(vdm, "\t\tstarttick \"{}\"", start);
+                let _ = writeln!(vdm, "\t\tskiptotick \"{}\"", to);
+            }
+            DemoAction::PlayCommands { start, name, commands } => {
+                let _ = writeln!(vdm, "\t\tfactory \"PlayCommands\"");
+                let _ = writeln!(vdm, "\t\tname \"{}\"", name);
+                let _ = writeln!(vdm, "\t\tstarttick \"{}\"", start);
+                let _ = writeln!(vdm, "\t\tcommands \"{}\"", commands);
+            }
+        }
+        let _ = writeln!(vdm, "\t}}");
+    }
+
+    vdm.push_str("}\n");
+    vdm
+}
+
+/// Write the highlight `.vdm` next to the demo, TF2 loads it automatically on playdemo
+pub fn write_vdm(demo_path: &Path, detections: &[Detection]) -> Result<PathBuf, String> {
+    if detections.is_empty() {
+        return Err("No detections to export".to_string());
+    }
+
+    let vdm_path = demo_path.with_extension("vdm");
+    std::fs::write(&vdm_path, build_vdm(detections))
+        .map_err(|e| format!("Failed to write {}: {}", vdm_path.display(), e))?;
+    Ok(vdm_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn detection(tick: u32, player: u64, name: &str, entity: Option<u32>) -> Detection {
+        Detection {
+            tick,
+            algorithm: "sniper_analyzer".to_string(),
+            player,
+            data: match entity {
+                Some(entity) => json!({ "player_name": name, "entity": entity }),
+                None => json!({ "player_name": name }),
+            },
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn close_detections_are_one_highlight() {
+        let detections = [
+            detection(5000, 1, "a", Some(3)),
+            detection(1000, 1, "a", Some(3)),
+            detection(1000, 1, "a", Some(3)), // same tick and player twice
+            detection(1000 + SLOWMO_AFTER + MERGE_TICKS, 2, "b", Some(4)),
+        ];
+        let merged = highlights(&detections);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].start, 1000 - LEAD_TICKS);
+        assert_eq!(merged[0].end, 1000 + 2 * SLOWMO_AFTER + MERGE_TICKS);
+        assert_eq!(merged[0].events.iter().map(|event| event.tick).collect::<Vec<_>>(), [1000, 1000 + SLOWMO_AFTER + MERGE_TICKS]);
+        assert_eq!((merged[1].start, merged[1].end), (5000 - LEAD_TICKS, 5000 + SLOWMO_AFTER));
+
+        // One tick further apart is a separate highlight
+        let apart = highlights(&[detection(1000, 1, "a", None), detection(1001 + SLOWMO_AFTER + MERGE_TICKS, 1, "a", None)]);
+        assert_eq!(apart.len(), 2);
+    }
+
+    #[test]
+    fn demoactions() {
+        let vdm = build_vdm(&[detection(1000, 1, "sus player", Some(3)), detection(1030, 1, "sus player", Some(3))]);
+        let expected = "\
+demoactions
+{
+\t\"1\"
+\t{
+\t\tfactory \"SkipAhead\"
+\t\tname \"skip1\"
+\t\tstarttick \"1\"
+\t\tskiptotick \"670\"
+\t}
+\t\"2\"
+\t{
+\t\tfactory \"PlayCommands\"
+\t\tname \"highlight1_tick1000\"
+\t\tstarttick \"934\"
+\t\tcommands \"echo sniper_analyzer flagged sus player at tick 1000; spec_player 3; spec_mode 4; host_timescale 0.25\"
+\t}
+\t\"3\"
+\t{
+\t\tfactory \"PlayCommands\"
+\t\tname \"highlight1_tick1030\"
+\t\tstarttick \"964\"
+\t\tcommands \"echo sniper_analyzer flagged sus player at tick 1030\"
+\t}
+\t\"4\"
+\t{
+\t\tfactory \"PlayCommands\"
+\t\tname \"highlight1_resume1\"
+\t\tstarttick \"1096\"
+\t\tcommands \"host_timescale 1\"
+\t}
+}
+";
+        assert_eq!(vdm, expected);
+    }
+
+    #[test]
+    fn no_spec_without_an_entity() {
+        let vdm = build_vdm(&[detection(100, 1, "name\"; quit", None)]);
+        assert!(!vdm.contains("spec_player"));
+        assert!(vdm.contains("commands \"echo sniper_analyzer flagged name quit at tick 100; host_timescale 0.25\""));
+        // Early detections start from the first tick without a skip
+        assert!(!vdm.contains("SkipAhead"));
+    }
+}